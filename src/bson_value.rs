@@ -0,0 +1,208 @@
+use anyhow::{Result, bail};
+use bson::Bson;
+use serde::{Serialize, Serializer};
+use serde_json::{Map, Value as JsonValue};
+use serde_yaml::Value as YamlValue;
+
+use crate::null_policy::yaml_key_name;
+use crate::number::to_exact_f64;
+
+/// Mode of MongoDB Extended JSON v2 used for bson values in other formats.
+#[derive(Debug, Copy, Clone, Default, PartialEq, clap::ValueEnum)]
+pub enum ExtJsonMode {
+    /// Native json numbers where possible, `{"$date": "<iso-8601>"}` for datetimes
+    #[default]
+    Relaxed,
+    /// Every number is typed like `{"$numberInt": "42"}`, lossless
+    Canonical,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct BsonLoadOptions {
+    pub extjson: ExtJsonMode,
+    /// Input is a stream of concatenated documents like `mongodump` output, it's loaded as an
+    /// array even when it has one document
+    pub stream: bool,
+}
+
+#[derive(Debug)]
+pub struct BsonWrapper {
+    pub document: Bson,
+    pub extjson: ExtJsonMode,
+}
+
+impl Serialize for BsonWrapper {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        let json = match self.extjson {
+            ExtJsonMode::Relaxed => self.document.clone().into_relaxed_extjson(),
+            ExtJsonMode::Canonical => self.document.clone().into_canonical_extjson(),
+        };
+        json.serialize(serializer)
+    }
+}
+
+/// Loads one document, or array of documents when input is loaded as a stream.
+pub fn load_bson(input: &[u8], options: &BsonLoadOptions) -> Result<BsonWrapper> {
+    let mut documents = Vec::new();
+    let mut rest = input;
+    while !rest.is_empty() {
+        let offset = input.len() - rest.len();
+        let length = match rest.first_chunk::<4>() {
+            Some(length) => i32::from_le_bytes(*length),
+            None => bail!("Truncated bson document at byte {offset}"),
+        };
+        let length = match usize::try_from(length) {
+            Ok(length) if length >= 5 && length <= rest.len() => length,
+            _ => bail!("Invalid bson document length {length} at byte {offset}"),
+        };
+        let (document, tail) = rest.split_at(length);
+        documents.push(Bson::Document(bson::deserialize_from_slice(document)?));
+        rest = tail;
+    }
+
+    let document = match (options.stream, documents.len()) {
+        (true, _) => Bson::Array(documents),
+        (false, 1) => documents.remove(0),
+        (false, 0) => bail!("Bson input is empty"),
+        (false, count) => {
+            bail!("Bson input has {count} documents, load it as a stream to get an array")
+        }
+    };
+    Ok(BsonWrapper {
+        document,
+        extjson: options.extjson,
+    })
+}
+
+/// Dumps document, array of documents is dumped as a stream of concatenated documents.
+pub fn dump_bson(document: &Bson) -> Result<Vec<u8>> {
+    match document {
+        Bson::Document(document) => Ok(bson::serialize_to_vec(document)?),
+        Bson::Array(items) => {
+            let mut buffer = Vec::new();
+            for item in items {
+                let Bson::Document(document) = item else {
+                    bail!(
+                        "Bson stream items must be documents, got {:?}",
+                        item.element_type()
+                    );
+                };
+                buffer.extend(bson::serialize_to_vec(document)?);
+            }
+            Ok(buffer)
+        }
+        document => bail!(
+            "Bson root must be a document or array of documents, got {:?}",
+            document.element_type()
+        ),
+    }
+}
+
+/// Extended json like `{"$oid": "..."}` is converted into bson types.
+pub fn json_to_bson(json: &[u8]) -> Result<Vec<u8>> {
+    let json: JsonValue = serde_json::from_slice(json)?;
+    extjson_to_bson(json)
+}
+
+pub(crate) fn extjson_to_bson(json: JsonValue) -> Result<Vec<u8>> {
+    dump_bson(&Bson::try_from(typed_numbers(json))?)
+}
+
+/// Json of yaml where NaN and infinity are kept as `{"$numberDouble": "NaN"}`.
+pub(crate) fn yaml_to_extjson(yaml: YamlValue) -> Result<JsonValue> {
+    let json = match yaml {
+        YamlValue::Number(n) if n.is_nan() => extjson("$numberDouble", "NaN".to_string()),
+        YamlValue::Number(n) if n.is_infinite() => {
+            let name = match n.as_f64() {
+                Some(f) if f > 0.0 => "Infinity",
+                _ => "-Infinity",
+            };
+            extjson("$numberDouble", name.to_string())
+        }
+        YamlValue::Sequence(items) => {
+            let items: Result<Vec<JsonValue>> = items.into_iter().map(yaml_to_extjson).collect();
+            JsonValue::Array(items?)
+        }
+        YamlValue::Mapping(map) => {
+            let mut object = Map::new();
+            for (key, value) in map {
+                object.insert(yaml_key_name(&key), yaml_to_extjson(value)?);
+            }
+            JsonValue::Object(object)
+        }
+        YamlValue::Tagged(tagged) => {
+            let mut object = Map::new();
+            object.insert(tagged.tag.to_string(), yaml_to_extjson(tagged.value)?);
+            JsonValue::Object(object)
+        }
+        yaml => serde_json::to_value(yaml)?,
+    };
+    Ok(json)
+}
+
+/// Float of extended json `{"$numberDouble": "..."}`.
+pub(crate) fn number_double(map: &Map<String, JsonValue>) -> Option<f64> {
+    match map.len() {
+        1 => map.get("$numberDouble")?.as_str()?.parse().ok(),
+        _ => None,
+    }
+}
+
+/// Plain integers become `int64` like serde does, numbers which don't fit into `int64` or
+/// `double` become `decimal128`, extended json values are kept as is.
+fn typed_numbers(json: JsonValue) -> JsonValue {
+    match json {
+        JsonValue::Number(n) if n.is_i64() => extjson("$numberLong", n.to_string()),
+        JsonValue::Number(n) if to_exact_f64(&n).is_err() => {
+            extjson("$numberDecimal", n.to_string())
+        }
+        JsonValue::Array(items) => JsonValue::Array(items.into_iter().map(typed_numbers).collect()),
+        JsonValue::Object(map) if !is_extjson(&map) => {
+            let map = map
+                .into_iter()
+                .map(|(key, value)| (key, typed_numbers(value)))
+                .collect();
+            JsonValue::Object(map)
+        }
+        json => json,
+    }
+}
+
+/// Keys of extended json wrappers like `{"$oid": "..."}`, legacy `$binary` with `$type`,
+/// `$regex` with `$options` and `$code` with `$scope` have two keys. Other `$` keys like
+/// `$set` of MongoDB updates are plain keys.
+const EXTJSON_KEYS: &[&str] = &[
+    "$oid",
+    "$symbol",
+    "$numberInt",
+    "$numberLong",
+    "$numberDouble",
+    "$numberDecimal",
+    "$binary",
+    "$type",
+    "$uuid",
+    "$code",
+    "$scope",
+    "$timestamp",
+    "$regularExpression",
+    "$regex",
+    "$options",
+    "$dbPointer",
+    "$date",
+    "$minKey",
+    "$maxKey",
+    "$undefined",
+];
+
+fn is_extjson(map: &Map<String, JsonValue>) -> bool {
+    !map.is_empty() && map.len() <= 2 && map.keys().all(|key| EXTJSON_KEYS.contains(&key.as_str()))
+}
+
+pub(crate) fn extjson(key: &str, value: String) -> JsonValue {
+    let mut map = Map::new();
+    map.insert(key.to_string(), JsonValue::String(value));
+    JsonValue::Object(map)
+}
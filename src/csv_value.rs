@@ -1,8 +1,14 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
 use std::iter::zip;
+use std::str::FromStr;
 
-use anyhow::{Result, bail};
+use anyhow::{Result, anyhow, bail};
 use serde::{Deserialize, Serialize};
-use serde_json::Value as JsonValue;
+use serde_json::{Map, Value as JsonValue};
+
+use crate::formatting::{Formatting, LineEnding};
+use crate::number::{is_integer, parse_number, to_exact_f64};
 
 #[derive(Debug, Deserialize)]
 pub struct CsvWrapper {
@@ -18,17 +24,141 @@ impl Serialize for CsvWrapper {
     }
 }
 
-pub fn load_csv(csv_bytes: &[u8]) -> Result<CsvWrapper> {
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ColumnType {
+    String,
+    Int,
+    Float,
+    Bool,
+    /// RFC 3339 date and/or time, kept as string
+    Datetime,
+}
+
+impl FromStr for ColumnType {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        let column_type = match s {
+            "string" | "str" => ColumnType::String,
+            "int" | "integer" => ColumnType::Int,
+            "float" | "number" => ColumnType::Float,
+            "bool" | "boolean" => ColumnType::Bool,
+            "datetime" | "date" | "time" => ColumnType::Datetime,
+            _ => bail!("Unknown csv column type: {s:?}"),
+        };
+        Ok(column_type)
+    }
+}
+
+impl fmt::Display for ColumnType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ColumnType::String => "string",
+            ColumnType::Int => "int",
+            ColumnType::Float => "float",
+            ColumnType::Bool => "bool",
+            ColumnType::Datetime => "datetime",
+        };
+        f.write_str(name)
+    }
+}
+
+impl ColumnType {
+    /// Converts string into value of this type, `None` if it doesn't match the type.
+    pub(crate) fn convert(self, s: &str) -> Option<JsonValue> {
+        match self {
+            ColumnType::String => Some(JsonValue::String(s.to_string())),
+            ColumnType::Int => parse_number(s).filter(is_integer).map(JsonValue::Number),
+            ColumnType::Float => parse_number(s)
+                .and_then(|n| match is_integer(&n) {
+                    // integers of float columns are written as floats, unless float rounds them
+                    true => to_exact_f64(&n).ok().and_then(serde_json::Number::from_f64),
+                    false => Some(n),
+                })
+                .map(JsonValue::Number),
+            ColumnType::Bool => s.parse::<bool>().ok().map(JsonValue::Bool),
+            ColumnType::Datetime => s
+                .parse::<toml::value::Datetime>()
+                .ok()
+                .map(|_| JsonValue::String(s.to_string())),
+        }
+    }
+}
+
+/// Parses column types spec like `id:string,price:float`. Entries may also be separated by newlines,
+/// so the same syntax is used for schema files.
+pub fn parse_column_types(spec: &str) -> Result<HashMap<String, ColumnType>> {
+    let mut column_types = HashMap::new();
+    for entry in spec.split([',', '\n']).map(str::trim) {
+        if entry.is_empty() || entry.starts_with('#') {
+            continue;
+        }
+        let (column, column_type) = entry.rsplit_once(':').ok_or_else(|| {
+            anyhow!("Invalid csv column type {entry:?}, expected <column>:<type>")
+        })?;
+        column_types.insert(column.trim().to_string(), column_type.trim().parse()?);
+    }
+    Ok(column_types)
+}
+
+#[derive(Debug, Copy, Clone, Default, PartialEq, clap::ValueEnum)]
+pub enum TypeMismatch {
+    /// Fail the conversion
+    #[default]
+    Error,
+    /// Replace the cell with null
+    Null,
+    /// Keep the cell as string
+    String,
+}
+
+#[derive(Debug, Clone)]
+pub struct CsvLoadOptions {
+    /// Rebuild nested objects and arrays from flattened column names
+    pub unflatten: bool,
+    pub key_separator: String,
+    /// Guess bool and number types of cells without explicit column type, otherwise keep strings
+    pub infer_types: bool,
+    pub column_types: HashMap<String, ColumnType>,
+    pub type_mismatch: TypeMismatch,
+}
+
+impl Default for CsvLoadOptions {
+    fn default() -> Self {
+        Self {
+            unflatten: false,
+            key_separator: ".".to_string(),
+            infer_types: true,
+            column_types: HashMap::new(),
+            type_mismatch: TypeMismatch::default(),
+        }
+    }
+}
+
+pub fn load_csv(csv_bytes: &[u8], options: &CsvLoadOptions) -> Result<CsvWrapper> {
     let mut reader = csv::Reader::from_reader(csv_bytes);
 
     let header: Vec<String> = reader.headers()?.iter().map(|s| s.to_string()).collect();
+    if let Some(column) = options.column_types.keys().find(|c| !header.contains(c)) {
+        bail!("Unknown csv column in types: {column:?}");
+    }
+
     let mut items = Vec::new();
     for record_result in reader.records() {
+        let record = record_result?;
         let mut record_map = serde_json::Map::new();
-        for (key, value) in zip(header.clone(), &record_result?) {
-            record_map.insert(key, parse_value(value.trim()));
+        for (key, value) in zip(header.clone(), &record) {
+            let value = convert_cell(&key, value.trim(), options).map_err(|err| {
+                let line = record.position().map_or(0, |p| p.line());
+                err.context(format!("Csv line {line}"))
+            })?;
+            record_map.insert(key, value);
+        }
+        if options.unflatten {
+            items.push(unflatten(record_map, &options.key_separator)?);
+        } else {
+            items.push(serde_json::Value::Object(record_map));
         }
-        items.push(serde_json::Value::Object(record_map));
     }
 
     Ok(CsvWrapper {
@@ -36,6 +166,27 @@ pub fn load_csv(csv_bytes: &[u8]) -> Result<CsvWrapper> {
     })
 }
 
+fn convert_cell(column: &str, s: &str, options: &CsvLoadOptions) -> Result<JsonValue> {
+    let Some(&column_type) = options.column_types.get(column) else {
+        if options.infer_types {
+            return Ok(parse_value(s));
+        }
+        return Ok(JsonValue::String(s.to_string()));
+    };
+    if s.is_empty() && column_type != ColumnType::String {
+        return Ok(JsonValue::Null);
+    }
+
+    match (column_type.convert(s), options.type_mismatch) {
+        (Some(value), _) => Ok(value),
+        (None, TypeMismatch::Error) => {
+            bail!("Value {s:?} of csv column {column:?} is not a valid {column_type}")
+        }
+        (None, TypeMismatch::Null) => Ok(JsonValue::Null),
+        (None, TypeMismatch::String) => Ok(JsonValue::String(s.to_string())),
+    }
+}
+
 fn parse_value(s: &str) -> JsonValue {
     if s.is_empty() {
         return JsonValue::Null;
@@ -43,60 +194,240 @@ fn parse_value(s: &str) -> JsonValue {
     if let Ok(b) = s.parse::<bool>() {
         return JsonValue::Bool(b);
     }
-    if let Ok(i) = s.parse::<i64>() {
-        return JsonValue::Number(i.into());
-    }
-    if let Ok(f) = s.parse::<f64>()
-        && f.is_finite()
-    {
-        return JsonValue::Number(serde_json::Number::from_f64(f).unwrap());
+    if let Some(n) = parse_number(s) {
+        return JsonValue::Number(n);
     }
     JsonValue::String(s.to_string())
 }
 
-pub fn json_to_csv(json: &[u8]) -> Result<Vec<u8>> {
-    let json: JsonValue = serde_json::from_slice(json)?;
-
-    if let JsonValue::Array(values) = json {
-        let mut buffer = String::new();
-        let mut dump_header: bool = true;
-
-        for item in values {
-            if let JsonValue::Object(items) = item {
-                let keys: Vec<String> = items.keys().cloned().collect();
-                if dump_header {
-                    dump_header = false;
-                    let mut first = true;
-                    for key in keys.clone() {
-                        if first {
-                            first = false;
-                        } else {
-                            buffer.push(',');
-                        }
-                        buffer.push_str(&key.to_string());
-                    }
-                    buffer.push('\n');
-                }
-                let mut first = true;
-                for key in keys {
-                    if let Some(value) = items.get(&key) {
-                        if first {
-                            first = false;
-                        } else {
-                            buffer.push(',');
-                        }
-                        buffer.push_str(&value.to_string());
-                    } else {
-                        bail!("Missing value for key: in {key:?}")
-                    }
+#[derive(Debug, Copy, Clone, Default, PartialEq, clap::ValueEnum)]
+pub enum ColumnOrder {
+    /// Columns appear in the order keys are first seen across all rows
+    #[default]
+    FirstSeen,
+    /// Columns are sorted alphabetically
+    Sorted,
+}
+
+#[derive(Debug, Copy, Clone, Default, PartialEq, clap::ValueEnum)]
+pub enum ArrayStyle {
+    /// Every array item gets its own column: `tags.0`, `tags.1`
+    #[default]
+    Indexed,
+    /// Array items are joined into a single cell
+    Joined,
+}
+
+#[derive(Debug, Clone)]
+pub struct CsvDumpOptions {
+    pub column_order: ColumnOrder,
+    /// Flatten nested objects and arrays into separate columns, otherwise they are dumped as json
+    pub flatten: bool,
+    pub array_style: ArrayStyle,
+    pub key_separator: String,
+    pub array_separator: String,
+}
+
+impl Default for CsvDumpOptions {
+    fn default() -> Self {
+        Self {
+            column_order: ColumnOrder::default(),
+            flatten: true,
+            array_style: ArrayStyle::default(),
+            key_separator: ".".to_string(),
+            array_separator: ";".to_string(),
+        }
+    }
+}
+
+/// Records end with line ending of `formatting`, line breaks of quoted cells are kept.
+pub fn json_to_csv(
+    json: &[u8],
+    options: &CsvDumpOptions,
+    formatting: &Formatting,
+) -> Result<Vec<u8>> {
+    let table = json_to_table(json, options)?;
+
+    let terminator = match formatting.line_ending {
+        LineEnding::Lf => csv::Terminator::Any(b'\n'),
+        LineEnding::Crlf => csv::Terminator::CRLF,
+    };
+    let mut writer = csv::WriterBuilder::new()
+        .terminator(terminator)
+        .from_writer(vec![]);
+    if !table.header.is_empty() {
+        writer.write_record(&table.header)?;
+    }
+    for row in &table.rows {
+        let mut record = Vec::with_capacity(table.header.len());
+        for key in &table.header {
+            record.push(match row.get(key) {
+                Some(value) => format_cell(value)?,
+                None => String::new(),
+            });
+        }
+        writer.write_record(&record)?;
+    }
+    writer.flush()?;
+    Ok(writer.into_inner().map_err(|e| e.into_error())?)
+}
+
+/// Array of objects with a common header, shared by tabular output formats.
+pub(crate) struct Table {
+    pub header: Vec<String>,
+    pub rows: Vec<Map<String, JsonValue>>,
+}
+
+pub(crate) fn json_to_table(json: &[u8], options: &CsvDumpOptions) -> Result<Table> {
+    value_to_table(serde_json::from_slice(json)?, options)
+}
+
+pub(crate) fn value_to_table(json: JsonValue, options: &CsvDumpOptions) -> Result<Table> {
+    let JsonValue::Array(values) = json else {
+        bail!("Invalid json format for tabular conversion. Expected root Array of items.")
+    };
+
+    let mut rows = Vec::with_capacity(values.len());
+    for item in values {
+        if let JsonValue::Object(items) = item {
+            if options.flatten {
+                let mut flat = Map::new();
+                for (key, value) in items {
+                    flatten_into(&mut flat, key, value, options)?;
                 }
+                rows.push(flat);
+            } else {
+                rows.push(items);
+            }
+        } else {
+            bail!("Invalid json format for tabular conversion: {item:?}")
+        }
+    }
+
+    let header = collect_header(&rows, options.column_order);
+    Ok(Table { header, rows })
+}
+
+/// Union of the keys of all rows, so rows with missing or extra keys still line up.
+fn collect_header(rows: &[Map<String, JsonValue>], order: ColumnOrder) -> Vec<String> {
+    let mut header: Vec<String> = Vec::new();
+    let mut seen = HashSet::new();
+    for row in rows {
+        for key in row.keys() {
+            if seen.insert(key.as_str()) {
+                header.push(key.clone());
+            }
+        }
+    }
+    if order == ColumnOrder::Sorted {
+        header.sort();
+    }
+    header
+}
+
+pub(crate) fn format_cell(value: &JsonValue) -> Result<String> {
+    let cell = match value {
+        JsonValue::Null => String::new(),
+        JsonValue::String(s) => s.clone(),
+        JsonValue::Bool(b) => b.to_string(),
+        JsonValue::Number(n) => n.to_string(),
+        JsonValue::Array(_) | JsonValue::Object(_) => serde_json::to_string(value)?,
+    };
+    Ok(cell)
+}
+
+fn flatten_into(
+    flat: &mut Map<String, JsonValue>,
+    column: String,
+    value: JsonValue,
+    options: &CsvDumpOptions,
+) -> Result<()> {
+    let sep = &options.key_separator;
+    match value {
+        JsonValue::Object(items) if !items.is_empty() => {
+            for (key, item) in items {
+                flatten_into(flat, format!("{column}{sep}{key}"), item, options)?;
+            }
+        }
+        JsonValue::Array(items) if options.array_style == ArrayStyle::Joined => {
+            let cells: Result<Vec<String>> = items.iter().map(format_cell).collect();
+            let joined = cells?.join(&options.array_separator);
+            insert_column(flat, column, JsonValue::String(joined))?;
+        }
+        JsonValue::Array(items) if !items.is_empty() => {
+            for (index, item) in items.into_iter().enumerate() {
+                flatten_into(flat, format!("{column}{sep}{index}"), item, options)?;
+            }
+        }
+        value => insert_column(flat, column, value)?,
+    }
+    Ok(())
+}
+
+/// Flattened keys like `a.b` of `{"a.b": 1, "a": {"b": 2}}` may name the same column.
+fn insert_column(
+    flat: &mut Map<String, JsonValue>,
+    column: String,
+    value: JsonValue,
+) -> Result<()> {
+    if flat.contains_key(&column) {
+        bail!("Csv column {column:?} is flattened from more than one key");
+    }
+    flat.insert(column, value);
+    Ok(())
+}
+
+fn unflatten(record: Map<String, JsonValue>, separator: &str) -> Result<JsonValue> {
+    if separator.is_empty() {
+        bail!("Separator of csv column names can't be empty");
+    }
+    let mut root = Map::new();
+    for (column, value) in record {
+        let mut path: Vec<&str> = column.split(separator).collect();
+        let last = path.pop().unwrap_or_default();
+        let mut map = &mut root;
+        for key in path {
+            let node = map
+                .entry(key.to_string())
+                .or_insert_with(|| JsonValue::Object(Map::new()));
+            let JsonValue::Object(inner) = node else {
+                bail!("Csv column {column:?} conflicts with another column");
+            };
+            map = inner;
+        }
+        // flattening writes empty arrays and objects as `[]` and `{}` cells
+        let value = match value.as_str() {
+            Some("[]") => JsonValue::Array(Vec::new()),
+            Some("{}") => JsonValue::Object(Map::new()),
+            _ => value,
+        };
+        if map.insert(last.to_string(), value).is_some() {
+            bail!("Csv column {column:?} conflicts with another column");
+        }
+    }
+    let root = root.into_iter().map(|(k, v)| (k, restore_arrays(v)));
+    Ok(JsonValue::Object(root.collect()))
+}
+
+/// Objects whose keys are exactly `0..n` came from flattened arrays.
+fn restore_arrays(value: JsonValue) -> JsonValue {
+    match value {
+        JsonValue::Object(map) => {
+            let is_array = !map.is_empty()
+                && map
+                    .keys()
+                    .enumerate()
+                    .all(|(index, key)| *key == index.to_string());
+            if is_array {
+                JsonValue::Array(map.into_iter().map(|(_, v)| restore_arrays(v)).collect())
             } else {
-                bail!("Invalid json format for csv conversion: {item:?}")
+                JsonValue::Object(
+                    map.into_iter()
+                        .map(|(k, v)| (k, restore_arrays(v)))
+                        .collect(),
+                )
             }
-            buffer.push('\n');
         }
-        Ok(buffer.into_bytes())
-    } else {
-        bail!("Invalid json format for csv conversion. Expected root Array of items.")
+        value => value,
     }
 }
@@ -0,0 +1,323 @@
+use anyhow::Result;
+use serde::Serialize;
+use serde_json::Value as JsonValue;
+use serde_json::ser::PrettyFormatter;
+
+#[derive(Debug, Copy, Clone, Default, PartialEq, clap::ValueEnum)]
+pub enum LineEnding {
+    /// Unix `\n`
+    #[default]
+    Lf,
+    /// Windows `\r\n`
+    Crlf,
+}
+
+impl LineEnding {
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            LineEnding::Lf => "\n",
+            LineEnding::Crlf => "\r\n",
+        }
+    }
+}
+
+/// Where line breaks of text output come from, line breaks inside of values are kept as they are.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub(crate) enum LineBreaks {
+    /// Line breaks are structural, or parsers of format normalize line breaks of values
+    Structural,
+    /// Multi-line strings of toml keep line breaks of values
+    Toml,
+    /// Writer of format puts the line ending itself (csv, sql)
+    Written,
+}
+
+/// Layout of text output, every format honours what its syntax and serializer allow.
+#[derive(Debug, Clone, Default)]
+pub struct Formatting {
+    /// Spaces per indentation level of pretty output, default of format when `None`
+    pub indent: Option<usize>,
+    /// Indent pretty output with tabs
+    pub indent_tabs: bool,
+    pub line_ending: LineEnding,
+    /// Output ends with a line ending
+    pub final_newline: bool,
+    /// Arrays and objects which fit into this width stay on one line in pretty json and toml
+    pub max_width: Option<usize>,
+}
+
+impl Formatting {
+    /// Indentation of one level, `default` of format unless it was set.
+    pub(crate) fn indent(&self, default: &str) -> String {
+        match (self.indent_tabs, self.indent) {
+            (true, _) => "\t".to_string(),
+            (false, Some(indent)) => " ".repeat(indent),
+            (false, None) => default.to_string(),
+        }
+    }
+
+    pub(crate) fn fits(&self, width: usize) -> bool {
+        self.max_width.is_some_and(|max_width| width <= max_width)
+    }
+
+    /// Applies line ending and final newline to text output.
+    pub(crate) fn finish(&self, mut output: Vec<u8>, line_breaks: LineBreaks) -> Vec<u8> {
+        if self.final_newline && !output.is_empty() && !output.ends_with(b"\n") {
+            output.extend(
+                match line_breaks {
+                    LineBreaks::Written => self.line_ending.as_str(),
+                    _ => "\n",
+                }
+                .as_bytes(),
+            );
+        }
+        match (self.line_ending, line_breaks) {
+            (LineEnding::Lf, _) | (LineEnding::Crlf, LineBreaks::Written) => output,
+            (LineEnding::Crlf, LineBreaks::Structural) => {
+                let mut crlf = Vec::with_capacity(output.len() + output.len() / 16);
+                for (i, &b) in output.iter().enumerate() {
+                    if b == b'\n' && (i == 0 || output[i - 1] != b'\r') {
+                        crlf.push(b'\r');
+                    }
+                    crlf.push(b);
+                }
+                crlf
+            }
+            (LineEnding::Crlf, LineBreaks::Toml) => toml_crlf(&output),
+        }
+    }
+
+    /// Replaces indentation of serializers with fixed indentation of `default` width. Only for
+    /// output where leading whitespace of lines is never part of values.
+    pub(crate) fn reindent(&self, output: String, default: &str) -> String {
+        let indent = self.indent(default);
+        if indent == default || default.is_empty() {
+            return output;
+        }
+        let mut reindented = String::with_capacity(output.len());
+        for line in output.split_inclusive('\n') {
+            let content = line.trim_start_matches(' ');
+            let depth = (line.len() - content.len()) / default.len();
+            reindented.push_str(&indent.repeat(depth));
+            reindented.push_str(content);
+        }
+        reindented
+    }
+}
+
+/// Converts line breaks of toml to CRLF, line breaks of strings are kept.
+fn toml_crlf(output: &[u8]) -> Vec<u8> {
+    const DELIMITERS: [&[u8]; 4] = [b"\"\"\"", b"'''", b"\"", b"'"];
+    let mut crlf = Vec::with_capacity(output.len() + output.len() / 16);
+    let mut string: Option<&[u8]> = None;
+    let mut i = 0;
+    while i < output.len() {
+        let rest = &output[i..];
+        let length = match string {
+            None => match DELIMITERS.into_iter().find(|d| rest.starts_with(d)) {
+                Some(delimiter) => {
+                    string = Some(delimiter);
+                    delimiter.len()
+                }
+                None => {
+                    if rest[0] == b'\n' && crlf.last() != Some(&b'\r') {
+                        crlf.push(b'\r');
+                    }
+                    1
+                }
+            },
+            // escapes of basic strings may escape quotes
+            Some(delimiter) if delimiter[0] == b'"' && rest[0] == b'\\' => rest.len().min(2),
+            Some(delimiter) if rest.starts_with(delimiter) => {
+                string = None;
+                // multi-line strings may end with up to two quotes before delimiter
+                let quotes = rest.iter().take_while(|&&b| b == delimiter[0]).count();
+                match delimiter.len() {
+                    3 => quotes.min(5),
+                    _ => 1,
+                }
+            }
+            Some(_) => 1,
+        };
+        crlf.extend(&rest[..length]);
+        i += length;
+    }
+    crlf
+}
+
+/// Joins lines of pretty output into one line like `{a: [1, 2], b: true}`, trailing commas are
+/// dropped. Only for output where strings can't span lines.
+pub(crate) fn collapse_lines(output: &str) -> String {
+    let lines: Vec<&str> = output.lines().map(str::trim).collect();
+    let mut collapsed = String::with_capacity(output.len());
+    for (i, line) in lines.iter().enumerate() {
+        let next = lines.get(i + 1).copied().unwrap_or_default();
+        match line.strip_suffix(',') {
+            Some(line) if next.starts_with(['}', ']']) => collapsed.push_str(line),
+            Some(_) => {
+                collapsed.push_str(line);
+                collapsed.push(' ');
+            }
+            None => collapsed.push_str(line),
+        }
+    }
+    collapsed
+}
+
+/// Pretty json, arrays and objects which fit into max width are written on one line.
+pub(crate) fn to_json_pretty<T: Serialize>(value: &T, formatting: &Formatting) -> Result<Vec<u8>> {
+    let indent = formatting.indent("  ");
+    if formatting.max_width.is_none() {
+        let mut buffer = Vec::new();
+        let formatter = PrettyFormatter::with_indent(indent.as_bytes());
+        let mut serializer = serde_json::Serializer::with_formatter(&mut buffer, formatter);
+        value.serialize(&mut serializer)?;
+        return Ok(buffer);
+    }
+
+    let json = serde_json::to_value(value)?;
+    let width = json_width(&json)?;
+    let mut writer = JsonWriter {
+        buffer: String::new(),
+        indent,
+        formatting,
+    };
+    writer.value(&json, &width, 0, 0)?;
+    Ok(writer.buffer.into_bytes())
+}
+
+/// Width of value written on one line and widths of its items, computed once bottom-up so that
+/// layout of nested values doesn't measure them again on every level.
+#[derive(Debug, Default)]
+pub(crate) struct InlineWidth {
+    pub(crate) width: usize,
+    pub(crate) items: Vec<InlineWidth>,
+}
+
+impl InlineWidth {
+    /// Width of items joined by `, ` between brackets of `brackets` width, `extra` is the sum of
+    /// widths of keys and other parts of items which aren't values.
+    pub(crate) fn join(items: Vec<InlineWidth>, brackets: usize, extra: usize) -> Self {
+        let separators = 2 * items.len().saturating_sub(1);
+        let width =
+            brackets + separators + extra + items.iter().map(|item| item.width).sum::<usize>();
+        InlineWidth { width, items }
+    }
+
+    pub(crate) fn scalar(text: &str) -> Self {
+        InlineWidth {
+            width: text.chars().count(),
+            items: Vec::new(),
+        }
+    }
+}
+
+/// Width of json written by `write_inline`.
+fn json_width(json: &JsonValue) -> Result<InlineWidth> {
+    let width = match json {
+        JsonValue::Array(items) => {
+            let items = items.iter().map(json_width).collect::<Result<_>>()?;
+            InlineWidth::join(items, "[]".len(), 0)
+        }
+        JsonValue::Object(map) if !map.is_empty() => {
+            let mut keys = 0;
+            let mut items = Vec::with_capacity(map.len());
+            for (key, value) in map {
+                keys += serde_json::to_string(key)?.chars().count() + ": ".len();
+                items.push(json_width(value)?);
+            }
+            InlineWidth::join(items, "{  }".len(), keys)
+        }
+        json => InlineWidth::scalar(&serde_json::to_string(json)?),
+    };
+    Ok(width)
+}
+
+struct JsonWriter<'a> {
+    buffer: String,
+    indent: String,
+    formatting: &'a Formatting,
+}
+
+impl JsonWriter<'_> {
+    /// `prefix` is the width of indentation and key before value on its line.
+    fn value(
+        &mut self,
+        json: &JsonValue,
+        width: &InlineWidth,
+        depth: usize,
+        prefix: usize,
+    ) -> Result<()> {
+        let is_nested = match json {
+            JsonValue::Array(items) => !items.is_empty(),
+            JsonValue::Object(map) => !map.is_empty(),
+            _ => false,
+        };
+        if !is_nested || self.formatting.fits(prefix + width.width) {
+            return self.inline(json);
+        }
+
+        let indent = self.indent.repeat(depth + 1);
+        let indent_width = indent.chars().count();
+        match json {
+            JsonValue::Array(items) => {
+                self.buffer.push('[');
+                for (i, (item, width)) in items.iter().zip(&width.items).enumerate() {
+                    self.buffer.push_str(if i == 0 { "\n" } else { ",\n" });
+                    self.buffer.push_str(&indent);
+                    self.value(item, width, depth + 1, indent_width)?;
+                }
+                self.close(depth, ']');
+            }
+            JsonValue::Object(map) => {
+                self.buffer.push('{');
+                for (i, ((key, value), width)) in map.iter().zip(&width.items).enumerate() {
+                    self.buffer.push_str(if i == 0 { "\n" } else { ",\n" });
+                    self.buffer.push_str(&indent);
+                    let key = format!("{}: ", serde_json::to_string(key)?);
+                    self.buffer.push_str(&key);
+                    self.value(value, width, depth + 1, indent_width + key.chars().count())?;
+                }
+                self.close(depth, '}');
+            }
+            _ => unreachable!("scalars are written inline"),
+        }
+        Ok(())
+    }
+
+    /// Json on one line with spaces after separators like in pretty output.
+    fn inline(&mut self, json: &JsonValue) -> Result<()> {
+        match json {
+            JsonValue::Array(items) => {
+                self.buffer.push('[');
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 {
+                        self.buffer.push_str(", ");
+                    }
+                    self.inline(item)?;
+                }
+                self.buffer.push(']');
+            }
+            JsonValue::Object(map) if !map.is_empty() => {
+                self.buffer.push_str("{ ");
+                for (i, (key, value)) in map.iter().enumerate() {
+                    if i > 0 {
+                        self.buffer.push_str(", ");
+                    }
+                    self.buffer.push_str(&serde_json::to_string(key)?);
+                    self.buffer.push_str(": ");
+                    self.inline(value)?;
+                }
+                self.buffer.push_str(" }");
+            }
+            json => self.buffer.push_str(&serde_json::to_string(json)?),
+        }
+        Ok(())
+    }
+
+    fn close(&mut self, depth: usize, bracket: char) {
+        self.buffer.push('\n');
+        self.buffer.push_str(&self.indent.repeat(depth));
+        self.buffer.push(bracket);
+    }
+}
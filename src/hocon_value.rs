@@ -1,4 +1,4 @@
-use anyhow::Result;
+use anyhow::{Result, bail};
 use hocon_::{Hocon, HoconLoader};
 use serde::{Deserialize, Serialize};
 
@@ -16,9 +16,10 @@ fn hocon_to_json(hocon: Hocon) -> Result<serde_json::Value> {
     match hocon {
         Hocon::Boolean(b) => Ok(serde_json::Value::Bool(b)),
         Hocon::Integer(i) => Ok(serde_json::Value::Number(serde_json::Number::from(i))),
-        Hocon::Real(f) => Ok(serde_json::Value::Number(
-            serde_json::Number::from_f64(f).unwrap(),
-        )),
+        Hocon::Real(f) => match serde_json::Number::from_f64(f) {
+            Some(n) => Ok(serde_json::Value::Number(n)),
+            None => bail!("Number {f} can't be represented in json"),
+        },
         Hocon::String(s) => Ok(serde_json::Value::String(s)),
         Hocon::Array(vec) => {
             let json_array: Result<Vec<serde_json::Value>> =
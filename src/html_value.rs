@@ -0,0 +1,159 @@
+use anyhow::Result;
+use serde_json::Value as JsonValue;
+
+use crate::csv_value::{CsvDumpOptions, format_cell, json_to_table};
+use crate::formatting::Formatting;
+use crate::xml_value::escape_xml;
+
+#[derive(Debug, Clone, Default)]
+pub struct HtmlDumpOptions {
+    /// Dump only the content without `<html>`, `<head>` and `<body>`
+    pub fragment: bool,
+    pub title: Option<String>,
+}
+
+const STYLE: &str = "table { border-collapse: collapse; } \
+th, td { border: 1px solid #ccc; padding: 4px 8px; text-align: left; } \
+dt { font-weight: bold; }";
+
+/// Dumps array of objects as html table, objects as nested definition lists and arrays as lists.
+pub fn json_to_html(
+    json: &[u8],
+    is_compact: bool,
+    options: &HtmlDumpOptions,
+    csv_options: &CsvDumpOptions,
+    formatting: &Formatting,
+) -> Result<Vec<u8>> {
+    let value: JsonValue = serde_json::from_slice(json)?;
+    let mut html = HtmlWriter {
+        buffer: String::new(),
+        is_compact,
+        indent: formatting.indent("  "),
+        depth: 0,
+    };
+
+    if !options.fragment {
+        let title = escape_xml(options.title.as_deref().unwrap_or("convfmt"));
+        html.line("<!DOCTYPE html>");
+        html.open("<html>");
+        html.open("<head>");
+        html.line("<meta charset=\"utf-8\">");
+        html.line(&format!("<title>{title}</title>"));
+        html.line(&format!("<style>{STYLE}</style>"));
+        html.close("</head>");
+        html.open("<body>");
+    }
+
+    match &value {
+        JsonValue::Array(items) if !items.is_empty() && items.iter().all(JsonValue::is_object) => {
+            dump_table(json, csv_options, &mut html)?;
+        }
+        value => dump_value(value, &mut html),
+    }
+
+    if !options.fragment {
+        html.close("</body>");
+        html.close("</html>");
+    }
+    Ok(html.buffer.into_bytes())
+}
+
+struct HtmlWriter {
+    buffer: String,
+    is_compact: bool,
+    indent: String,
+    depth: usize,
+}
+
+impl HtmlWriter {
+    fn line(&mut self, content: &str) {
+        if !self.is_compact {
+            self.buffer.push_str(&self.indent.repeat(self.depth));
+        }
+        self.buffer.push_str(content);
+        if !self.is_compact {
+            self.buffer.push('\n');
+        }
+    }
+
+    fn open(&mut self, tag: &str) {
+        self.line(tag);
+        self.depth += 1;
+    }
+
+    fn close(&mut self, tag: &str) {
+        self.depth -= 1;
+        self.line(tag);
+    }
+}
+
+fn dump_table(json: &[u8], options: &CsvDumpOptions, html: &mut HtmlWriter) -> Result<()> {
+    let table = json_to_table(json, options)?;
+
+    html.open("<table>");
+    html.open("<thead>");
+    html.open("<tr>");
+    for key in &table.header {
+        html.line(&format!("<th>{}</th>", escape_xml(key)));
+    }
+    html.close("</tr>");
+    html.close("</thead>");
+    html.open("<tbody>");
+    for row in &table.rows {
+        html.open("<tr>");
+        for key in &table.header {
+            let cell = match row.get(key) {
+                Some(value) => format_cell(value)?,
+                None => String::new(),
+            };
+            html.line(&format!("<td>{}</td>", escape_xml(&cell)));
+        }
+        html.close("</tr>");
+    }
+    html.close("</tbody>");
+    html.close("</table>");
+    Ok(())
+}
+
+fn dump_value(value: &JsonValue, html: &mut HtmlWriter) {
+    match value {
+        JsonValue::Object(items) if !items.is_empty() => {
+            html.open("<dl>");
+            for (key, item) in items {
+                html.line(&format!("<dt>{}</dt>", escape_xml(key)));
+                dump_item("dd", item, html);
+            }
+            html.close("</dl>");
+        }
+        JsonValue::Array(items) if !items.is_empty() => {
+            html.open("<ul>");
+            for item in items {
+                dump_item("li", item, html);
+            }
+            html.close("</ul>");
+        }
+        scalar => html.line(&format_scalar(scalar)),
+    }
+}
+
+fn dump_item(tag: &str, item: &JsonValue, html: &mut HtmlWriter) {
+    let is_nested = match item {
+        JsonValue::Object(items) => !items.is_empty(),
+        JsonValue::Array(items) => !items.is_empty(),
+        _ => false,
+    };
+    if is_nested {
+        html.open(&format!("<{tag}>"));
+        dump_value(item, html);
+        html.close(&format!("</{tag}>"));
+    } else {
+        html.line(&format!("<{tag}>{}</{tag}>", format_scalar(item)));
+    }
+}
+
+fn format_scalar(value: &JsonValue) -> String {
+    match value {
+        JsonValue::String(s) => escape_xml(s),
+        value => value.to_string(),
+    }
+}
@@ -1,8 +1,9 @@
 use anyhow::{Result, bail};
-use serde::ser::SerializeSeq;
 use serde::{Deserialize, Serialize};
 use serde_json::Value as JsonValue;
 
+/// Items are serialized as a plain array, so jsonl converts into arrays of other formats and
+/// into bson streams of documents.
 #[derive(Debug, Deserialize)]
 pub struct JsonlWrapper {
     pub items: Vec<JsonValue>,
@@ -13,12 +14,7 @@ impl Serialize for JsonlWrapper {
     where
         S: serde::Serializer,
     {
-        let mut seq = serializer.serialize_seq(Some(self.items.len()))?;
-        for item in &self.items {
-            seq.serialize_element(&item)?;
-            seq.serialize_element(&'\n')?;
-        }
-        seq.end()
+        self.items.serialize(serializer)
     }
 }
 
@@ -0,0 +1,73 @@
+use serde_json::{Map, Value as JsonValue};
+use serde_yaml::{Mapping, Value as YamlValue};
+
+use crate::null_policy::yaml_key_name;
+
+/// Order of object keys in output, by default keys keep the order of input.
+#[derive(Debug, Clone, Default)]
+pub struct KeyOrder {
+    /// Sort keys of all objects
+    pub sort: bool,
+    /// Keys which go first in this order in every object, other keys follow them
+    pub first: Vec<String>,
+}
+
+impl KeyOrder {
+    pub(crate) fn is_preserved(&self) -> bool {
+        !self.sort && self.first.is_empty()
+    }
+
+    /// Stable sort of map entries, keys which aren't named in `first` keep their order unless
+    /// all keys are sorted.
+    pub(crate) fn sort_entries<K, V>(&self, entries: &mut [(K, V)], name: impl Fn(&K) -> String) {
+        entries.sort_by_cached_key(|(key, _)| {
+            let name = name(key);
+            let rank = self.first.iter().position(|first| *first == name);
+            (rank.unwrap_or(self.first.len()), self.sort.then_some(name))
+        });
+    }
+
+    pub(crate) fn order_json(&self, json: JsonValue) -> JsonValue {
+        match json {
+            JsonValue::Array(items) => JsonValue::Array(
+                items
+                    .into_iter()
+                    .map(|item| self.order_json(item))
+                    .collect(),
+            ),
+            JsonValue::Object(map) => {
+                let mut entries: Vec<(String, JsonValue)> = map
+                    .into_iter()
+                    .map(|(key, value)| (key, self.order_json(value)))
+                    .collect();
+                self.sort_entries(&mut entries, String::clone);
+                JsonValue::Object(entries.into_iter().collect::<Map<_, _>>())
+            }
+            json => json,
+        }
+    }
+
+    pub(crate) fn order_yaml(&self, yaml: YamlValue) -> YamlValue {
+        match yaml {
+            YamlValue::Sequence(items) => YamlValue::Sequence(
+                items
+                    .into_iter()
+                    .map(|item| self.order_yaml(item))
+                    .collect(),
+            ),
+            YamlValue::Mapping(map) => {
+                let mut entries: Vec<(YamlValue, YamlValue)> = map
+                    .into_iter()
+                    .map(|(key, value)| (key, self.order_yaml(value)))
+                    .collect();
+                self.sort_entries(&mut entries, yaml_key_name);
+                YamlValue::Mapping(entries.into_iter().collect::<Mapping>())
+            }
+            YamlValue::Tagged(mut tagged) => {
+                tagged.value = self.order_yaml(tagged.value);
+                YamlValue::Tagged(tagged)
+            }
+            yaml => yaml,
+        }
+    }
+}
@@ -1,18 +1,75 @@
+mod bson_value;
 mod csv_value;
+mod formatting;
 #[cfg(feature = "hocon")]
 mod hocon_value;
+mod html_value;
 mod jsonl_value;
+mod key_order;
+mod markdown_value;
+mod nan_policy;
+mod null_policy;
+mod number;
+mod plist_value;
+#[cfg(feature = "spreadsheet")]
+mod spreadsheet_value;
+mod sql_value;
+#[cfg(feature = "sqlite")]
+mod sqlite_value;
+mod toml_value;
+mod xml_schema;
 mod xml_value;
+mod yaml_value;
 
-use anyhow::Result;
+use std::fmt;
+
+use anyhow::{Result, bail};
+use clap::ValueEnum;
 use serde::Serialize;
 
 #[cfg(feature = "hocon")]
 use crate::hocon_value::{HoconWrapper, load_hocon};
+#[cfg(feature = "spreadsheet")]
+use crate::spreadsheet_value::{SpreadsheetWrapper, json_to_xlsx, load_spreadsheet};
+#[cfg(feature = "sqlite")]
+use crate::sqlite_value::{SqliteWrapper, json_to_sqlite, load_sqlite};
 use crate::{
+    bson_value::{
+        BsonWrapper, dump_bson, extjson_to_bson, json_to_bson, load_bson, yaml_to_extjson,
+    },
     csv_value::{CsvWrapper, json_to_csv, load_csv},
+    formatting::{LineBreaks, collapse_lines, to_json_pretty},
+    html_value::json_to_html,
     jsonl_value::{JsonlWrapper, json_to_jsonl, load_jsonl},
+    markdown_value::json_to_markdown,
+    nan_policy::replace_nan,
+    null_policy::{has_yaml_nulls, replace_nulls, replace_yaml_nulls},
+    number::NativeNumbers,
+    plist_value::{PlistWrapper, dump_plist, extjson_to_plist, json_to_plist, load_plist},
+    sql_value::json_to_sql,
+    toml_value::{TomlWrapper, dump_toml, load_toml, toml_to_bson, value_to_toml},
     xml_value::{XmlWrapper, json_to_xml, load_xml},
+    yaml_value::{to_yaml, to_yaml_flow},
+};
+
+pub use crate::bson_value::{BsonLoadOptions, ExtJsonMode};
+pub use crate::csv_value::{
+    ArrayStyle, ColumnOrder, ColumnType, CsvDumpOptions, CsvLoadOptions, TypeMismatch,
+    parse_column_types,
+};
+pub use crate::formatting::{Formatting, LineEnding};
+pub use crate::html_value::HtmlDumpOptions;
+pub use crate::key_order::KeyOrder;
+pub use crate::nan_policy::NanPolicy;
+pub use crate::null_policy::NullPolicy;
+pub use crate::plist_value::PlistDumpOptions;
+pub use crate::sql_value::{SqlDialect, SqlDumpOptions};
+#[cfg(feature = "sqlite")]
+pub use crate::sqlite_value::SqliteLoadOptions;
+pub use crate::toml_value::TomlDumpOptions;
+pub use crate::xml_schema::XmlSchema;
+pub use crate::xml_value::{
+    XmlConvention, XmlDumpOptions, XmlLoadOptions, XmlMapping, XmlNamespaces,
 };
 
 #[derive(Debug, Copy, Clone, PartialEq, clap::ValueEnum)]
@@ -22,102 +79,481 @@ pub enum Format {
     Hjson,
     #[cfg(feature = "hocon")]
     Hocon,
+    /// Output only
+    Html,
     Json,
     Json5,
     Jsonl,
+    /// Output only
+    Markdown,
+    /// Input only
+    #[cfg(feature = "spreadsheet")]
+    Ods,
     Plist,
     Ron,
+    /// Output only
+    Sql,
+    #[cfg(feature = "sqlite")]
+    Sqlite,
     Toml,
     Toon,
+    #[cfg(feature = "spreadsheet")]
+    Xlsx,
     Xml,
     Yaml,
 }
 
+/// How a format honours compact output.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum CompactOutput {
+    /// Output is pretty unless compact output is requested
+    Optional,
+    /// Output has a single layout which is already compact
+    Always,
+    /// Output has a single layout which isn't compact, requests for compact output are ignored
+    Unsupported,
+}
+
+impl Format {
+    /// Formats which can be loaded, others are output only.
+    pub fn is_input(self) -> bool {
+        !matches!(self, Format::Html | Format::Markdown | Format::Sql)
+    }
+
+    /// Formats which can be dumped, others are input only.
+    pub fn is_output(self) -> bool {
+        match self {
+            #[cfg(feature = "spreadsheet")]
+            Format::Ods => false,
+            _ => true,
+        }
+    }
+
+    /// How output of the format follows `DumpOptions::is_compact`.
+    pub fn compact_output(self) -> CompactOutput {
+        match self {
+            Format::Bson | Format::Csv | Format::Jsonl => CompactOutput::Always,
+            #[cfg(feature = "spreadsheet")]
+            Format::Ods | Format::Xlsx => CompactOutput::Always,
+            #[cfg(feature = "sqlite")]
+            Format::Sqlite => CompactOutput::Always,
+            Format::Markdown | Format::Toon => CompactOutput::Unsupported,
+            _ => CompactOutput::Optional,
+        }
+    }
+
+    /// Formats without null need a `NullPolicy` to dump values with nulls.
+    pub fn has_null(self) -> bool {
+        !matches!(self, Format::Plist | Format::Toml)
+    }
+
+    /// Text formats follow `Formatting` of output, plist is text unless it's binary.
+    pub fn is_text(self) -> bool {
+        match self {
+            Format::Bson => false,
+            #[cfg(feature = "spreadsheet")]
+            Format::Ods | Format::Xlsx => false,
+            #[cfg(feature = "sqlite")]
+            Format::Sqlite => false,
+            _ => true,
+        }
+    }
+
+    fn line_breaks(self) -> LineBreaks {
+        match self {
+            Format::Csv | Format::Sql => LineBreaks::Written,
+            Format::Toml => LineBreaks::Toml,
+            _ => LineBreaks::Structural,
+        }
+    }
+
+    /// Formats without NaN and infinity need a `NanPolicy` to dump such floats.
+    pub fn has_nan(self) -> bool {
+        matches!(
+            self,
+            Format::Bson
+                | Format::Json5
+                | Format::Plist
+                | Format::Ron
+                | Format::Toml
+                | Format::Yaml
+        )
+    }
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.to_possible_value() {
+            Some(value) => f.write_str(value.get_name()),
+            None => write!(f, "{self:?}"),
+        }
+    }
+}
+
 #[derive(Debug, Serialize)]
 #[serde(untagged)]
 pub enum Value {
-    Bson(bson::Bson),
+    Bson(BsonWrapper),
     Csv(CsvWrapper),
     Hjson(serde_hjson::Value),
     #[cfg(feature = "hocon")]
     Hocon(HoconWrapper),
     Json(serde_json::Value),
-    Json5(serde_json::Value),
+    /// Yaml value keeps NaN and infinity of json5
+    Json5(serde_yaml::Value),
     Jsonl(JsonlWrapper),
-    Plist(plist::Value),
+    #[cfg(feature = "spreadsheet")]
+    Ods(SpreadsheetWrapper),
+    Plist(PlistWrapper),
     Ron(ron::Value),
-    Toml(toml::Value),
+    #[cfg(feature = "sqlite")]
+    Sqlite(SqliteWrapper),
+    Toml(TomlWrapper),
     Toon(serde_json::Value),
+    #[cfg(feature = "spreadsheet")]
+    Xlsx(SpreadsheetWrapper),
     Xml(XmlWrapper),
     Yaml(serde_yaml::Value),
 }
 
+impl Value {
+    /// Json based values may have numbers of arbitrary precision but no NaN and infinity.
+    fn is_json_based(&self) -> bool {
+        !matches!(
+            self,
+            Value::Hjson(_)
+                | Value::Json5(_)
+                | Value::Plist(_)
+                | Value::Ron(_)
+                | Value::Toml(_)
+                | Value::Yaml(_)
+        )
+    }
+}
+
+/// Value for serializers other than serde_json. Json based values are converted to have
+/// numbers serialized as native integers and floats.
+enum NativeValue<'a> {
+    Value(&'a Value),
+    Json {
+        json: serde_json::Value,
+        is_i64_only: bool,
+    },
+}
+
+impl<'a> NativeValue<'a> {
+    fn new(value: &'a Value, format: Format) -> Result<Self> {
+        let native = match value {
+            value if value.is_json_based() => NativeValue::Json {
+                json: serde_json::to_value(value)?,
+                is_i64_only: format == Format::Toml,
+            },
+            value => NativeValue::Value(value),
+        };
+        Ok(native)
+    }
+}
+
+impl Serialize for NativeValue<'_> {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        match self {
+            NativeValue::Value(value) => value.serialize(serializer),
+            NativeValue::Json { json, is_i64_only } => {
+                NativeNumbers::new(json, *is_i64_only).serialize(serializer)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct LoadOptions {
+    pub bson: BsonLoadOptions,
+    pub csv: CsvLoadOptions,
+    /// Name or zero-based index of spreadsheet sheet
+    pub sheet: Option<String>,
+    #[cfg(feature = "sqlite")]
+    pub sqlite: SqliteLoadOptions,
+    pub xml: XmlLoadOptions,
+}
+
 pub fn load_input(input: &[u8], format: Format) -> Result<Value> {
+    load_input_with_options(input, format, &LoadOptions::default())
+}
+
+pub fn load_input_with_options(
+    input: &[u8],
+    format: Format,
+    options: &LoadOptions,
+) -> Result<Value> {
     let value = match format {
-        Format::Bson => Value::Bson(bson::deserialize_from_slice(input)?),
-        Format::Csv => Value::Csv(load_csv(input)?),
+        Format::Bson => Value::Bson(load_bson(input, &options.bson)?),
+        Format::Csv => Value::Csv(load_csv(input, &options.csv)?),
         Format::Hjson => Value::Hjson(serde_hjson::from_slice(input)?),
         #[cfg(feature = "hocon")]
         Format::Hocon => Value::Hocon(load_hocon(input)?),
+        Format::Html => bail!("html is supported only as output format"),
         Format::Json => Value::Json(serde_json::from_slice(input)?),
         Format::Json5 => Value::Json5(json5::from_str(str::from_utf8(input)?)?),
         Format::Jsonl => Value::Jsonl(load_jsonl(input)?),
-        Format::Plist => Value::Plist(plist::from_bytes(input)?),
+        Format::Markdown => bail!("markdown is supported only as output format"),
+        #[cfg(feature = "spreadsheet")]
+        Format::Ods => Value::Ods(load_spreadsheet(input, options.sheet.as_deref())?),
+        Format::Plist => Value::Plist(load_plist(input)?),
         Format::Ron => Value::Ron(ron::de::from_bytes(input)?),
-        Format::Toml => {
-            let s = std::str::from_utf8(input)?;
-            Value::Toml(toml::from_str(s)?)
-        }
+        Format::Sql => bail!("sql is supported only as output format"),
+        #[cfg(feature = "sqlite")]
+        Format::Sqlite => Value::Sqlite(load_sqlite(input, &options.sqlite)?),
+        Format::Toml => Value::Toml(load_toml(input)?),
         Format::Toon => {
             let s = std::str::from_utf8(input)?;
             Value::Toon(toon_format::decode_default(s)?)
         }
-        Format::Xml => Value::Xml(load_xml(input)?),
+        #[cfg(feature = "spreadsheet")]
+        Format::Xlsx => Value::Xlsx(load_spreadsheet(input, options.sheet.as_deref())?),
+        Format::Xml => Value::Xml(load_xml(input, &options.xml)?),
         Format::Yaml => Value::Yaml(serde_yaml::from_slice(input)?),
     };
     Ok(value)
 }
 
+#[derive(Debug, Clone, Default)]
+pub struct DumpOptions {
+    pub is_compact: bool,
+    /// Order of object keys, input order by default
+    pub key_order: KeyOrder,
+    pub formatting: Formatting,
+    /// What to do with NaN and infinity when format doesn't support them
+    pub nan_policy: NanPolicy,
+    /// What to do with nulls when format doesn't support them
+    pub null_policy: NullPolicy,
+    /// Replacement of nulls for `NullPolicy::Sentinel`
+    pub null_sentinel: String,
+    pub csv: CsvDumpOptions,
+    pub html: HtmlDumpOptions,
+    pub plist: PlistDumpOptions,
+    pub sql: SqlDumpOptions,
+    pub toml: TomlDumpOptions,
+    /// Name of spreadsheet sheet
+    pub sheet: Option<String>,
+    pub xml: XmlDumpOptions,
+}
+
 pub fn dump_value(value: &Value, format: Format, is_compact: bool) -> Result<Vec<u8>> {
-    let dumped: Vec<u8> = match (format, is_compact) {
-        (Format::Bson, _) => bson::serialize_to_vec(value)?,
+    let options = DumpOptions {
+        is_compact,
+        ..Default::default()
+    };
+    dump_value_with_options(value, format, &options)
+}
+
+pub fn dump_value_with_options(
+    value: &Value,
+    format: Format,
+    options: &DumpOptions,
+) -> Result<Vec<u8>> {
+    let ordered = order_keys(value, &options.key_order)?;
+    let value = ordered.as_ref().unwrap_or(value);
+    let replaced = replace_unsupported(value, format, options)?;
+    let value = replaced.as_ref().unwrap_or(value);
+
+    let dumped: Vec<u8> = match (format, options.is_compact) {
+        (Format::Bson, _) => match value {
+            Value::Bson(bson) => dump_bson(&bson.document)?,
+            Value::Toml(toml) => toml_to_bson(toml)?,
+            value if !value.is_json_based() => {
+                extjson_to_bson(yaml_to_extjson(serde_yaml::to_value(value)?)?)?
+            }
+            value => {
+                let json_dumped = serde_json::to_vec(value)?;
+                json_to_bson(&json_dumped)?
+            }
+        },
         (Format::Csv, _) => {
             let json_dumped = serde_json::to_vec(value)?;
-            json_to_csv(&json_dumped)?
+            json_to_csv(&json_dumped, &options.csv, &options.formatting)?
         }
-        (Format::Hjson, _) => serde_hjson::to_vec(value)?,
+        // json is valid hjson and has no line breaks
+        (Format::Hjson, true) => serde_json::to_vec(value)?,
+        (Format::Hjson, false) => serde_hjson::to_vec(&NativeValue::new(value, format)?)?,
         #[cfg(feature = "hocon")]
         (Format::Hocon, true) => serde_json::to_vec(value)?,
         #[cfg(feature = "hocon")]
-        (Format::Hocon, false) => serde_json::to_vec_pretty(value)?,
+        (Format::Hocon, false) => to_json_pretty(value, &options.formatting)?,
+        (Format::Html, is_compact) => {
+            let json_dumped = serde_json::to_vec(value)?;
+            json_to_html(
+                &json_dumped,
+                is_compact,
+                &options.html,
+                &options.csv,
+                &options.formatting,
+            )?
+        }
         (Format::Json, true) => serde_json::to_vec(value)?,
-        (Format::Json, false) => serde_json::to_vec_pretty(value)?,
-        (Format::Json5, _) => json5::to_string(value).map(|e| e.into_bytes())?,
+        (Format::Json, false) => to_json_pretty(value, &options.formatting)?,
+        (Format::Json5, true) => {
+            let json5 = json5::to_string(&NativeValue::new(value, format)?)?;
+            collapse_lines(&json5).into_bytes()
+        }
+        (Format::Json5, false) => {
+            let json5 = json5::to_string(&NativeValue::new(value, format)?)?;
+            // strings of json5 are escaped, so leading whitespace is always indentation
+            options.formatting.reindent(json5, "  ").into_bytes()
+        }
         (Format::Jsonl, _) => {
             let json_dumped = serde_json::to_vec(value)?;
             json_to_jsonl(&json_dumped)?
         }
-        (Format::Plist, _) => {
-            let mut buffer = Vec::new();
-            plist::to_writer_xml(&mut buffer, value)?;
-            buffer
+        (Format::Markdown, _) => {
+            let json_dumped = serde_json::to_vec(value)?;
+            json_to_markdown(&json_dumped, &options.csv)?
+        }
+        #[cfg(feature = "spreadsheet")]
+        (Format::Ods, _) => bail!("ods is supported only as input format"),
+        (Format::Plist, is_compact) => match value {
+            Value::Plist(plist) => {
+                dump_plist(plist, is_compact, &options.plist, &options.formatting)?
+            }
+            Value::Toml(toml) => extjson_to_plist(
+                toml.to_extjson(),
+                is_compact,
+                &options.plist,
+                &options.formatting,
+            )?,
+            value if !value.is_json_based() => extjson_to_plist(
+                yaml_to_extjson(serde_yaml::to_value(value)?)?,
+                is_compact,
+                &options.plist,
+                &options.formatting,
+            )?,
+            value => {
+                let json_dumped = serde_json::to_vec(value)?;
+                json_to_plist(
+                    &json_dumped,
+                    is_compact,
+                    &options.plist,
+                    &options.formatting,
+                )?
+            }
+        },
+        (Format::Ron, true) => {
+            ron::ser::to_string(&NativeValue::new(value, format)?).map(|e| e.into_bytes())?
         }
-        (Format::Ron, true) => ron::ser::to_string(value).map(|e| e.into_bytes())?,
         (Format::Ron, false) => ron::ser::to_string_pretty(
-            value,
-            ron::ser::PrettyConfig::default().new_line("\n".to_owned()),
+            &NativeValue::new(value, format)?,
+            ron::ser::PrettyConfig::default()
+                .new_line("\n".to_owned())
+                .indentor(options.formatting.indent("    ")),
         )
         .map(|e| e.into_bytes())?,
-        (Format::Toml, true) => toml::to_string(value).map(|e| e.into_bytes())?,
-        (Format::Toml, false) => toml::to_string_pretty(value).map(|e| e.into_bytes())?,
+        (Format::Sql, is_compact) => {
+            let json_dumped = serde_json::to_vec(value)?;
+            json_to_sql(
+                &json_dumped,
+                is_compact,
+                &options.sql,
+                &options.csv,
+                &options.formatting,
+            )?
+        }
+        #[cfg(feature = "sqlite")]
+        (Format::Sqlite, _) => {
+            let json_dumped = serde_json::to_vec(value)?;
+            json_to_sqlite(&json_dumped, &options.sql, &options.csv)?
+        }
+        (Format::Toml, is_compact) => match value {
+            Value::Toml(toml) => dump_toml(toml, is_compact, &options.toml, &options.formatting)?,
+            Value::Plist(plist) => value_to_toml(
+                &NativeValue::Json {
+                    json: plist.to_extjson(),
+                    is_i64_only: true,
+                },
+                is_compact,
+                &options.toml,
+                &options.formatting,
+            )?,
+            value => value_to_toml(
+                &NativeValue::new(value, format)?,
+                is_compact,
+                &options.toml,
+                &options.formatting,
+            )?,
+        },
         (Format::Toon, _) => toon_format::encode_default(value)?.as_bytes().to_vec(),
-        (Format::Xml, _) => {
+        #[cfg(feature = "spreadsheet")]
+        (Format::Xlsx, _) => {
+            let json_dumped = serde_json::to_vec(value)?;
+            json_to_xlsx(&json_dumped, options.sheet.as_deref(), &options.csv)?
+        }
+        (Format::Xml, is_compact) => {
             let json_dumped = serde_json::to_vec(value)?;
-            json_to_xml(&json_dumped)?
+            json_to_xml(&json_dumped, is_compact, &options.xml, &options.formatting)?
+        }
+        (Format::Yaml, true) => to_yaml_flow(&NativeValue::new(value, format)?)?,
+        (Format::Yaml, false) => to_yaml(&NativeValue::new(value, format)?)?,
+    };
+    let is_text = format.is_text() && !(format == Format::Plist && options.plist.binary);
+    match is_text {
+        true => Ok(options.formatting.finish(dumped, format.line_breaks())),
+        false => Ok(dumped),
+    }
+}
+
+/// Reorders keys of all objects, `None` if keys keep order of input. Plist and toml values keep
+/// their types, values of other formats are converted like in `replace_unsupported`.
+fn order_keys(value: &Value, order: &KeyOrder) -> Result<Option<Value>> {
+    if order.is_preserved() {
+        return Ok(None);
+    }
+    let ordered = match value {
+        Value::Plist(plist) => Value::Plist(plist.with_key_order(order)),
+        Value::Toml(toml) => Value::Toml(toml.with_key_order(order)),
+        value if value.is_json_based() => {
+            Value::Json(order.order_json(serde_json::to_value(value)?))
         }
-        (Format::Yaml, _) => serde_yaml::to_string(value).map(|e| e.into_bytes())?,
+        value => Value::Yaml(order.order_yaml(serde_yaml::to_value(value)?)),
     };
-    Ok(dumped)
+    Ok(Some(ordered))
+}
+
+/// Applies NaN and null policies when format can't write such values, `None` if value is
+/// dumped as is. Values of other formats are converted only when they have such values to keep
+/// their types like datetimes.
+fn replace_unsupported(
+    value: &Value,
+    format: Format,
+    options: &DumpOptions,
+) -> Result<Option<Value>> {
+    let is_same_format = matches!(
+        (value, format),
+        (Value::Plist(_), Format::Plist) | (Value::Toml(_), Format::Toml)
+    );
+    if is_same_format || (format.has_nan() && format.has_null()) {
+        return Ok(None);
+    }
+
+    if value.is_json_based() {
+        if format.has_null() {
+            return Ok(None);
+        }
+        let json = serde_json::to_value(value)?;
+        let json = replace_nulls(json, options.null_policy, &options.null_sentinel, format)?;
+        return Ok(Some(Value::Json(json)));
+    }
+
+    let mut yaml = serde_yaml::to_value(value)?;
+    let mut is_replaced = false;
+    if !format.has_nan() {
+        is_replaced |= replace_nan(&mut yaml, options.nan_policy, format)?;
+    }
+    if !format.has_null() && has_yaml_nulls(&yaml) {
+        yaml = replace_yaml_nulls(yaml, options.null_policy, &options.null_sentinel, format)?;
+        is_replaced = true;
+    }
+    Ok(is_replaced.then_some(Value::Yaml(yaml)))
 }
 
 #[cfg(test)]
@@ -132,7 +568,8 @@ mod tests {
                 "A\0\0\0\u{4}array\0\u{17}\0\0\0\u{2}0\0\u{2}\0\0\0a\0\u{2}1\0\u{2}\0\0\0b\0\0\u{8}boolean\0\0\u{12}the_answer\0*\0\0\0\0\0\0\0\0"
             }
             (Format::Csv, _) => unimplemented!("use raw data for tests"),
-            (Format::Hjson, _) => {
+            (Format::Hjson, true) => r#"{"array":["a","b"],"boolean":false,"the_answer":42}"#,
+            (Format::Hjson, false) => {
                 r#"{
   array:
   [
@@ -162,7 +599,8 @@ the_answer: 42
   "the_answer": 42
 }"#
             }
-            (Format::Json5, _) => {
+            (Format::Json5, true) => r#"{array: ["a", "b"], boolean: false, the_answer: 42}"#,
+            (Format::Json5, false) => {
                 r#"{
   array: [
     "a",
@@ -172,8 +610,21 @@ the_answer: 42
   the_answer: 42,
 }"#
             }
-            (Format::Jsonl, _) => unimplemented!("use raw data for tests"),
-            (Format::Plist, _) => {
+            (Format::Html | Format::Jsonl | Format::Markdown | Format::Sql, _) => {
+                unimplemented!("use raw data for tests")
+            }
+            #[cfg(feature = "spreadsheet")]
+            (Format::Ods | Format::Xlsx, _) => unimplemented!("use raw data for tests"),
+            #[cfg(feature = "sqlite")]
+            (Format::Sqlite, _) => unimplemented!("use raw data for tests"),
+            (Format::Plist, true) => {
+                r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict><key>array</key><array><string>a</string><string>b</string></array><key>boolean</key><false/><key>the_answer</key><integer>42</integer></dict>
+</plist>"#
+            }
+            (Format::Plist, false) => {
                 r#"<?xml version="1.0" encoding="UTF-8"?>
 <!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
 <plist version="1.0">
@@ -224,7 +675,8 @@ the_answer: 42"#
             (Format::Xml, _) => {
                 r#"<root><array>a</array><array>b</array><boolean>false</boolean><the_answer>42</the_answer></root>"#
             }
-            (Format::Yaml, _) => {
+            (Format::Yaml, true) => "{array: [a, b], boolean: false, the_answer: 42}\n",
+            (Format::Yaml, false) => {
                 r#"array:
 - a
 - b
@@ -264,6 +716,10 @@ the_answer: 42
     #[case(Format::Yaml, Format::Toon, true)]
     #[case(Format::Json, Format::Plist, true)]
     #[case(Format::Plist, Format::Yaml, true)]
+    #[case(Format::Json, Format::Yaml, true)]
+    #[case(Format::Yaml, Format::Hjson, true)]
+    #[case(Format::Plist, Format::Plist, true)]
+    #[case(Format::Json5, Format::Plist, false)]
     fn test_convert_formats(
         #[case] from_format: Format,
         #[case] to_format: Format,
@@ -288,86 +744,1919 @@ the_answer: 42
 
     #[rstest]
     #[case(
-        Format::Csv,
-        Format::Json,
-        r#"age,immortal,name,power
-55000,true,Gendalf,50.0
-50,false,Frodo,5.0
-"#,
-        r#"[
-  {
-    "age": 55000,
-    "immortal": true,
-    "name": "Gendalf",
-    "power": 50.0
-  },
-  {
-    "age": 50,
-    "immortal": false,
-    "name": "Frodo",
-    "power": 5.0
-  }
-]"#,
-        false
+        ExtJsonMode::Relaxed,
+        r#"{"_id":{"$oid":"65f0c3a1b2c3d4e5f6a7b8c9"},"born":{"$date":"2968-09-22T00:00:00Z"},"rings":1,"power":{"$numberDecimal":"5.50"},"age":50}"#
     )]
     #[case(
-        Format::Csv,
-        Format::Json,
-        r#"age,immortal,name,power,test_empty
-55000, true, Gendalf, 50.0,
-50, false, Frodo, 5.0,
-"#,
-        r#"[{"age":55000,"immortal":true,"name":"Gendalf","power":50.0,"test_empty":null},{"age":50,"immortal":false,"name":"Frodo","power":5.0,"test_empty":null}]"#,
-        true
+        ExtJsonMode::Canonical,
+        r#"{"_id":{"$oid":"65f0c3a1b2c3d4e5f6a7b8c9"},"born":{"$date":{"$numberLong":"31516732800000"}},"rings":{"$numberInt":"1"},"power":{"$numberDecimal":"5.50"},"age":{"$numberLong":"50"}}"#
     )]
+    fn test_convert_bson_extjson(#[case] extjson: ExtJsonMode, #[case] expected_output: &str) {
+        let input = r#"{"_id":{"$oid":"65f0c3a1b2c3d4e5f6a7b8c9"},"born":{"$date":"2968-09-22T00:00:00Z"},"rings":{"$numberInt":"1"},"power":{"$numberDecimal":"5.50"},"age":50}"#;
+        let value = load_input(input.as_bytes(), Format::Json).unwrap();
+        let bson = dump_value(&value, Format::Bson, false).unwrap();
+
+        let options = LoadOptions {
+            bson: BsonLoadOptions {
+                extjson,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let value = load_input_with_options(&bson, Format::Bson, &options).unwrap();
+        let output = dump_value(&value, Format::Json, true).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), expected_output);
+
+        // relaxed mode loses int32 type of small numbers
+        if extjson == ExtJsonMode::Canonical {
+            let value = load_input(expected_output.as_bytes(), Format::Json).unwrap();
+            assert_eq!(dump_value(&value, Format::Bson, false).unwrap(), bson);
+        }
+    }
+
+    #[test]
+    fn test_convert_bson_operator_keys() {
+        let input = r#"{"$set":{"count":5000000000,"price":0.1000000000000000055},"$inc":{"$numberInt":"1"}}"#;
+        let value = load_input(input.as_bytes(), Format::Json).unwrap();
+        let bson = dump_value(&value, Format::Bson, false).unwrap();
+
+        let options = LoadOptions {
+            bson: BsonLoadOptions {
+                extjson: ExtJsonMode::Canonical,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let value = load_input_with_options(&bson, Format::Bson, &options).unwrap();
+        let output = dump_value(&value, Format::Json, true).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            r#"{"$set":{"count":{"$numberLong":"5000000000"},"price":{"$numberDecimal":"0.1000000000000000055"}},"$inc":{"$numberInt":"1"}}"#
+        );
+    }
+
+    #[test]
+    fn test_convert_bson_stream() {
+        let input = r#"{"name":"Frodo","_id":{"$oid":"65f0c3a1b2c3d4e5f6a7b8c9"}}
+{"name":"Sam"}
+"#;
+        let value = load_input(input.as_bytes(), Format::Jsonl).unwrap();
+        let stream = dump_value(&value, Format::Bson, false).unwrap();
+
+        let frodo = load_input(
+            r#"{"name":"Frodo","_id":{"$oid":"65f0c3a1b2c3d4e5f6a7b8c9"}}"#.as_bytes(),
+            Format::Json,
+        )
+        .unwrap();
+        let frodo = dump_value(&frodo, Format::Bson, false).unwrap();
+        assert!(stream.starts_with(&frodo));
+
+        let options = LoadOptions {
+            bson: BsonLoadOptions {
+                stream: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let value = load_input_with_options(&stream, Format::Bson, &options).unwrap();
+        let output = dump_value(&value, Format::Jsonl, false).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), input);
+        assert_eq!(dump_value(&value, Format::Bson, false).unwrap(), stream);
+
+        // stream of one document is still an array
+        let value = load_input_with_options(&frodo, Format::Bson, &options).unwrap();
+        let output = dump_value(&value, Format::Json, true).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            r#"[{"name":"Frodo","_id":{"$oid":"65f0c3a1b2c3d4e5f6a7b8c9"}}]"#
+        );
+
+        let err = load_input(&stream, Format::Bson).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Bson input has 2 documents, load it as a stream to get an array"
+        );
+        assert!(
+            load_input_with_options(&stream[..stream.len() - 1], Format::Bson, &options).is_err()
+        );
+        let value = load_input("[1, 2]".as_bytes(), Format::Json).unwrap();
+        assert!(dump_value(&value, Format::Bson, false).is_err());
+    }
+
+    #[test]
+    fn test_convert_plist_binary() {
+        let input = r#"{"name":"Frodo","born":"2968-09-22T00:00:00Z","key":"AQID"}"#;
+        let value = load_input(input.as_bytes(), Format::Json).unwrap();
+        let options = DumpOptions {
+            is_compact: true,
+            plist: PlistDumpOptions {
+                binary: true,
+                data_keys: vec!["key".to_string()],
+                dates: true,
+            },
+            ..Default::default()
+        };
+        let binary = dump_value_with_options(&value, Format::Plist, &options).unwrap();
+        assert!(binary.starts_with(b"bplist00"));
+
+        let value = load_input(&binary, Format::Plist).unwrap();
+        let xml = dump_value(&value, Format::Plist, false).unwrap();
+        let xml = String::from_utf8(xml).unwrap();
+        assert!(xml.contains("<date>2968-09-22T00:00:00Z</date>"));
+        assert!(xml.contains("<data>\n\tAQID\n\t</data>"));
+
+        let value = load_input(xml.as_bytes(), Format::Plist).unwrap();
+        let output = dump_value(&value, Format::Json, true).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), input);
+
+        let ascii = r#"{ name = Frodo; rings = (One, "Two"); }"#;
+        let value = load_input(ascii.as_bytes(), Format::Plist).unwrap();
+        let output = dump_value(&value, Format::Json, true).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            r#"{"name":"Frodo","rings":["One","Two"]}"#
+        );
+
+        let value = load_input(r#"{"key":"not base64!"}"#.as_bytes(), Format::Json).unwrap();
+        assert!(dump_value_with_options(&value, Format::Plist, &options).is_err());
+    }
+
+    #[rstest]
+    #[case(false, "<string>2968-09-22T00:00:00Z</string>")]
+    #[case(true, "<date>2968-09-22T00:00:00Z</date>")]
+    fn test_convert_plist_dates(#[case] dates: bool, #[case] expected: &str) {
+        let input = r#"{"born":"2968-09-22T00:00:00Z"}"#;
+        let value = load_input(input.as_bytes(), Format::Json).unwrap();
+        let options = DumpOptions {
+            plist: PlistDumpOptions {
+                dates,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let xml = dump_value_with_options(&value, Format::Plist, &options).unwrap();
+        let xml = String::from_utf8(xml).unwrap();
+        assert!(xml.contains(expected));
+
+        let value = load_input(xml.as_bytes(), Format::Plist).unwrap();
+        let output = dump_value(&value, Format::Json, true).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), input);
+
+        // extended json dates of bson are always dates
+        let input = r#"{"born":{"$date":"2968-09-22T00:00:00Z"}}"#;
+        let value = load_input(input.as_bytes(), Format::Json).unwrap();
+        let xml = dump_value_with_options(&value, Format::Plist, &options).unwrap();
+        let xml = String::from_utf8(xml).unwrap();
+        assert!(xml.contains("<date>2968-09-22T00:00:00Z</date>"));
+    }
+
+    #[rstest]
     #[case(
         Format::Json,
-        Format::Csv,
-        r#"[{"age":55000,"immortal":true,"name":"Gendalf the \"White\"","power":50.0},{"age":50,"immortal":false,"name":"Frodo","power":5.0}]"#,
-        r#"age,immortal,name,power
-55000,true,"Gendalf the \"White\"",50.0
-50,false,"Frodo",5.0
-"#,
-        true
+        r#"{"born":"1979-05-27T07:32:00Z","day":"1979-05-27","time":"07:32:00"}"#
     )]
+    #[case(
+        Format::Yaml,
+        "{born: 1979-05-27T07:32:00Z, day: 1979-05-27, time: 07:32:00}\n"
+    )]
+    #[case(
+        Format::Toml,
+        "born = 1979-05-27T07:32:00Z\nday = 1979-05-27\ntime = 07:32:00\n"
+    )]
+    fn test_convert_toml_datetimes(#[case] to: Format, #[case] expected_output: &str) {
+        let input = "born = 1979-05-27T07:32:00Z\nday = 1979-05-27\ntime = 07:32:00\n";
+        let value = load_input(input.as_bytes(), Format::Toml).unwrap();
+        let output = dump_value(&value, to, true).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), expected_output);
+    }
+
+    #[rstest]
     #[case(
         Format::Json,
-        Format::Json,
-        r#"[{"age":55000,"immortal":true,"name":"Gendalf the \"White\"","power":50.0},{"age":50,"immortal":false,"name":"Frodo","power":5.0}]"#,
-        r#"[{"age":55000,"immortal":true,"name":"Gendalf the \"White\"","power":50.0},{"age":50,"immortal":false,"name":"Frodo","power":5.0}]"#,
+        r#"{"born":"1979-05-27T07:32:00Z","time":"07:32:00"}"#,
         true
     )]
+    #[case(Format::Yaml, "born: 1979-05-27T07:32:00Z\ntime: 07:32:00\n", true)]
     #[case(
-        Format::Jsonl,
-        Format::Xml,
-        r#"{"age":55000,"immortal":true,"name":"Gendalf the \"White\"","power":50.0}
-{"age":50,"immortal":false,"name":"Frodo","power":5.0}
-"#,
-    r#"<root><age>55000</age><immortal>true</immortal><name>Gendalf the &quot;White&quot;</name><power>50.0</power></root>
-<root><age>50</age><immortal>false</immortal><name>Frodo</name><power>5.0</power></root>
-"#,
+        Format::Plist,
+        "<plist><dict><key>born</key><date>1979-05-27T07:32:00Z</date>\
+         <key>time</key><string>07:32:00</string></dict></plist>",
         false
     )]
+    fn test_convert_to_toml_datetimes(
+        #[case] from: Format,
+        #[case] input: &str,
+        #[case] has_date_strings: bool,
+    ) {
+        let value = load_input(input.as_bytes(), from).unwrap();
+        let options = DumpOptions {
+            is_compact: true,
+            toml: TomlDumpOptions {
+                datetimes: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let output = dump_value_with_options(&value, Format::Toml, &options).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "born = 1979-05-27T07:32:00Z\ntime = \"07:32:00\"\n"
+        );
+
+        // strings are kept without datetimes option, dates of plist aren't strings
+        let output = dump_value(&value, Format::Toml, true).unwrap();
+        assert_eq!(
+            String::from_utf8(output)
+                .unwrap()
+                .starts_with("born = 1979"),
+            !has_date_strings
+        );
+    }
+
+    #[rstest]
+    #[case(
+        TomlDumpOptions::default(),
+        r#"{"a":{"b":{"c":1}},"d":[{"e":[{"f":1}]}],"g":[1,{"h":2}],"i":1}"#,
+        "g = [1, { h = 2 }]\ni = 1\n\n[a.b]\nc = 1\n\n[[d]]\n\n[[d.e]]\nf = 1\n"
+    )]
     #[case(
-    Format::Json,
-    Format::Jsonl,
-    r#"[{"age":55000,"immortal":true,"name":"Gendalf the \"White\"","power":50.0},{"age":50,"immortal":false,"name":"Frodo","power":5.0}]"#,
-    r#"{"age":55000,"immortal":true,"name":"Gendalf the \"White\"","power":50.0}
-{"age":50,"immortal":false,"name":"Frodo","power":5.0}
-"#,
-    false
-)]
-    fn test_raw_convert(
-        #[case] from_format: Format,
-        #[case] to_format: Format,
+        TomlDumpOptions {
+            root_key: Some("items".to_string()),
+            ..Default::default()
+        },
+        r#"[{"a":1},{"a":2}]"#,
+        "[[items]]\na = 1\n\n[[items]]\na = 2\n"
+    )]
+    #[case(
+        TomlDumpOptions {
+            inline_depth: Some(1),
+            ..Default::default()
+        },
+        r#"{"a":{"b":{"c":1}},"d":[{"e":[{"f":1}]}]}"#,
+        "[a]\nb = { c = 1 }\n\n[[d]]\ne = [{ f = 1 }]\n"
+    )]
+    #[case(
+        TomlDumpOptions {
+            inline_size: Some(12),
+            ..Default::default()
+        },
+        r#"{"a":{"b":1},"c":{"d":"long string"},"e":[{"f":1}]}"#,
+        "a = { b = 1 }\ne = [{ f = 1 }]\n\n[c]\nd = \"long string\"\n"
+    )]
+    fn test_dump_toml_layout(
+        #[case] toml: TomlDumpOptions,
         #[case] input: &str,
         #[case] expected_output: &str,
-        #[case] is_compact: bool,
     ) {
-        let value = load_input(input.as_bytes(), from_format).unwrap();
-        let output = String::from_utf8(dump_value(&value, to_format, is_compact).unwrap()).unwrap();
+        let value = load_input(input.as_bytes(), Format::Json).unwrap();
+        let options = DumpOptions {
+            is_compact: true,
+            toml,
+            ..Default::default()
+        };
+        let output = dump_value_with_options(&value, Format::Toml, &options).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), expected_output);
+    }
 
-        assert_eq!(output, expected_output);
+    #[rstest]
+    #[case(
+        r#"[{"a":1}]"#,
+        "Root value of toml must be a table, array can be written under a root key"
+    )]
+    #[case(
+        r#"{"a":[1,{"b":18446744073709551615}]}"#,
+        "Value at a[1].b can't be written in toml: Integer 18446744073709551615 is out of range of i64"
+    )]
+    fn test_dump_toml_errors(#[case] input: &str, #[case] expected_error: &str) {
+        let value = load_input(input.as_bytes(), Format::Json).unwrap();
+        let err = dump_value(&value, Format::Toml, true).unwrap_err();
+        assert_eq!(err.to_string(), expected_error);
+    }
+
+    #[test]
+    fn test_convert_toml_datetimes_to_typed_formats() {
+        let input = "born = 1979-05-27T07:32:00Z\nday = 1979-05-27\n";
+        let value = load_input(input.as_bytes(), Format::Toml).unwrap();
+
+        let bson = dump_value(&value, Format::Bson, true).unwrap();
+        let value = load_input(&bson, Format::Bson).unwrap();
+        let output = dump_value(&value, Format::Json, true).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            r#"{"born":{"$date":"1979-05-27T07:32:00Z"},"day":"1979-05-27"}"#
+        );
+        let output = dump_value(&value, Format::Toml, true).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "born = 1979-05-27T07:32:00Z\nday = \"1979-05-27\"\n"
+        );
+
+        let value = load_input(input.as_bytes(), Format::Toml).unwrap();
+        let plist = dump_value(&value, Format::Plist, true).unwrap();
+        let plist = String::from_utf8(plist).unwrap();
+        assert!(plist.contains("<date>1979-05-27T07:32:00Z</date>"));
+    }
+
+    #[rstest]
+    #[case(
+        Format::Yaml,
+        Format::Toml,
+        NanPolicy::Error,
+        "a = nan\nb = [inf, -inf]\n"
+    )]
+    #[case(
+        Format::Yaml,
+        Format::Json5,
+        NanPolicy::Error,
+        "{a: NaN, b: [Infinity, -Infinity]}"
+    )]
+    #[case(
+        Format::Yaml,
+        Format::Ron,
+        NanPolicy::Error,
+        r#"{"a":NaN,"b":[inf,-inf]}"#
+    )]
+    #[case(
+        Format::Json5,
+        Format::Yaml,
+        NanPolicy::Error,
+        "{a: .nan, b: [.inf, -.inf]}\n"
+    )]
+    #[case(
+        Format::Toml,
+        Format::Json,
+        NanPolicy::Null,
+        r#"{"a":null,"b":[null,null]}"#
+    )]
+    #[case(
+        Format::Yaml,
+        Format::Json,
+        NanPolicy::String,
+        r#"{"a":"NaN","b":["Infinity","-Infinity"]}"#
+    )]
+    fn test_nan_policy(
+        #[case] from: Format,
+        #[case] to: Format,
+        #[case] nan_policy: NanPolicy,
+        #[case] expected: &str,
+    ) {
+        let input = match from {
+            Format::Yaml => "a: .nan\nb: [.inf, -.inf]\n",
+            Format::Json5 => "{a: NaN, b: [Infinity, -Infinity]}",
+            Format::Toml => "a = nan\nb = [inf, -inf]\n",
+            _ => unimplemented!(),
+        };
+        let value = load_input(input.as_bytes(), from).unwrap();
+        let options = DumpOptions {
+            is_compact: true,
+            nan_policy,
+            ..Default::default()
+        };
+        let output = dump_value_with_options(&value, to, &options).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), expected);
+    }
+
+    #[test]
+    fn test_nan_policy_error() {
+        let value = load_input("a: [1, .nan]\n".as_bytes(), Format::Yaml).unwrap();
+        let err = dump_value(&value, Format::Json, false).unwrap_err();
+        assert_eq!(err.to_string(), "NaN at a[1] can't be written in json");
+        assert!(dump_value(&value, Format::Csv, false).is_err());
+    }
+
+    #[rstest]
+    #[case(Format::Yaml, "a: .nan\nb:\n- .inf\n- -.inf\n")]
+    #[case(Format::Toml, "a = nan\nb = [inf, -inf]\n")]
+    #[case(Format::Json5, "{a: NaN, b: [Infinity, -Infinity]}")]
+    fn test_nan_kept(#[case] from: Format, #[case] input: &str) {
+        let value = load_input(input.as_bytes(), from).unwrap();
+
+        let bson = dump_value(&value, Format::Bson, false).unwrap();
+        let loaded = load_input(&bson, Format::Bson).unwrap();
+        let output = dump_value(&loaded, Format::Json, true).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            r#"{"a":{"$numberDouble":"NaN"},"b":[{"$numberDouble":"Infinity"},{"$numberDouble":"-Infinity"}]}"#
+        );
+
+        let plist = dump_value(&value, Format::Plist, true).unwrap();
+        assert!(
+            String::from_utf8(plist.clone())
+                .unwrap()
+                .contains("<real>NaN</real>")
+        );
+        let value = load_input(&plist, Format::Plist).unwrap();
+        let output = dump_value(&value, Format::Yaml, false).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "a: .nan\nb:\n- .inf\n- -.inf\n"
+        );
+    }
+
+    #[rstest]
+    #[case(NullPolicy::Drop, "b = [1]\n\n[c]\n")]
+    #[case(NullPolicy::Empty, "a = \"\"\nb = [1, \"\"]\n\n[c]\nd = \"\"\n")]
+    #[case(
+        NullPolicy::Sentinel,
+        "a = \"N/A\"\nb = [1, \"N/A\"]\n\n[c]\nd = \"N/A\"\n"
+    )]
+    fn test_null_policy(#[case] null_policy: NullPolicy, #[case] expected: &str) {
+        let input = r#"{"a":null,"b":[1,null],"c":{"d":null}}"#;
+        let value = load_input(input.as_bytes(), Format::Json).unwrap();
+        let options = DumpOptions {
+            is_compact: true,
+            null_policy,
+            null_sentinel: "N/A".to_string(),
+            ..Default::default()
+        };
+        let output = dump_value_with_options(&value, Format::Toml, &options).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), expected);
+    }
+
+    #[rstest]
+    #[case(
+        NullPolicy::Error,
+        r#"{"a":{"b":[1,null]}}"#,
+        "Null at a.b[1] can't be written in toml"
+    )]
+    #[case(
+        NullPolicy::DropKeys,
+        r#"{"a":{"b":[1,null]}}"#,
+        "Null at a.b[1] can't be written in toml"
+    )]
+    #[case(NullPolicy::Drop, "null", "Root value of toml can't be null")]
+    fn test_null_policy_errors(
+        #[case] null_policy: NullPolicy,
+        #[case] input: &str,
+        #[case] expected: &str,
+    ) {
+        let value = load_input(input.as_bytes(), Format::Json).unwrap();
+        let options = DumpOptions {
+            null_policy,
+            ..Default::default()
+        };
+        let err = dump_value_with_options(&value, Format::Toml, &options).unwrap_err();
+        assert_eq!(err.to_string(), expected);
+        assert!(dump_value_with_options(&value, Format::Plist, &options).is_err());
+        assert!(dump_value_with_options(&value, Format::Yaml, &options).is_ok());
+    }
+
+    #[rstest]
+    #[case(
+        Format::Json,
+        Format::Json,
+        r#"{"u64":18446744073709551615,"i128":-170141183460469231731687303715884105728,"big":123456789012345678901234567890123456789012,"decimal":0.1000000000000000055}"#,
+        r#"{"u64":18446744073709551615,"i128":-170141183460469231731687303715884105728,"big":123456789012345678901234567890123456789012,"decimal":0.1000000000000000055}"#
+    )]
+    #[case(
+        Format::Csv,
+        Format::Json,
+        "id,decimal\n123456789012345678901234,0.1000000000000000055\n",
+        r#"[{"id":123456789012345678901234,"decimal":0.1000000000000000055}]"#
+    )]
+    #[case(
+        Format::Json,
+        Format::Csv,
+        r#"[{"id":123456789012345678901234,"decimal":0.1000000000000000055}]"#,
+        "id,decimal\n123456789012345678901234,0.1000000000000000055\n"
+    )]
+    #[case(
+        Format::Json,
+        Format::Yaml,
+        r#"{"u64":18446744073709551615,"i128":-170141183460469231731687303715884105728,"float":0.5}"#,
+        "{u64: 18446744073709551615, i128: -170141183460469231731687303715884105728, float: 0.5}\n"
+    )]
+    #[case(
+        Format::Yaml,
+        Format::Json,
+        "u64: 18446744073709551615\ni64: -9223372036854775808\n",
+        r#"{"u64":18446744073709551615,"i64":-9223372036854775808}"#
+    )]
+    #[case(
+        Format::Json,
+        Format::Toml,
+        r#"{"i64":-9223372036854775808,"float":1e3}"#,
+        "i64 = -9223372036854775808\nfloat = 1000.0\n"
+    )]
+    fn test_convert_numbers(
+        #[case] from: Format,
+        #[case] to: Format,
+        #[case] input: &str,
+        #[case] expected: &str,
+    ) {
+        let value = load_input(input.as_bytes(), from).unwrap();
+        let output = dump_value(&value, to, true).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), expected);
+    }
+
+    #[rstest]
+    #[case(
+        true,
+        "{big: 123456789012345678901234567890123456789012, decimals: [0.1000000000000000055, 0.5]}\n"
+    )]
+    #[case(
+        false,
+        "big: 123456789012345678901234567890123456789012\ndecimals:\n- 0.1000000000000000055\n- 0.5\n"
+    )]
+    fn test_dump_yaml_exact_numbers(#[case] is_compact: bool, #[case] expected: &str) {
+        let input = r#"{"big":123456789012345678901234567890123456789012,"decimals":[0.1000000000000000055,0.5]}"#;
+        let value = load_input(input.as_bytes(), Format::Json).unwrap();
+        let output = dump_value(&value, Format::Yaml, is_compact).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), expected);
+    }
+
+    #[rstest]
+    #[case("a = 1_000.25\nb = +1.5e3\nc = [0.1, -inf]\n", None)]
+    #[case(
+        "[[table]]\nfloats = [0.5, 0.1000000000000000055]\n",
+        Some("Float at table.0.floats.1 can't be loaded")
+    )]
+    fn test_load_toml_inexact_floats(#[case] input: &str, #[case] expected_error: Option<&str>) {
+        let result = load_input(input.as_bytes(), Format::Toml);
+        assert_eq!(
+            result.err().map(|err| err.to_string()).as_deref(),
+            expected_error
+        );
+    }
+
+    #[rstest]
+    #[case(Format::Toml, r#"{"u64":18446744073709551615}"#)]
+    #[case(Format::Toml, r#"{"float":1e400}"#)]
+    #[cfg_attr(
+        feature = "spreadsheet",
+        case(Format::Xlsx, r#"[{"id":123456789012345678901234}]"#)
+    )]
+    fn test_dump_inexact_numbers(#[case] to: Format, #[case] input: &str) {
+        let value = load_input(input.as_bytes(), Format::Json).unwrap();
+        assert!(dump_value(&value, to, true).is_err());
+    }
+
+    #[cfg(feature = "hocon")]
+    #[test]
+    fn test_load_hocon_infinity() {
+        assert!(load_input("a = 1e999".as_bytes(), Format::Hocon).is_err());
+    }
+
+    #[rstest]
+    #[case(
+        Format::Csv,
+        Format::Json,
+        r#"age,immortal,name,power
+55000,true,Gendalf,50.0
+50,false,Frodo,5.0
+"#,
+        r#"[
+  {
+    "age": 55000,
+    "immortal": true,
+    "name": "Gendalf",
+    "power": 50.0
+  },
+  {
+    "age": 50,
+    "immortal": false,
+    "name": "Frodo",
+    "power": 5.0
+  }
+]"#,
+        false
+    )]
+    #[case(
+        Format::Csv,
+        Format::Json,
+        r#"age,immortal,name,power,test_empty
+55000, true, Gendalf, 50.0,
+50, false, Frodo, 5.0,
+"#,
+        r#"[{"age":55000,"immortal":true,"name":"Gendalf","power":50.0,"test_empty":null},{"age":50,"immortal":false,"name":"Frodo","power":5.0,"test_empty":null}]"#,
+        true
+    )]
+    #[case(
+        Format::Json,
+        Format::Csv,
+        r#"[{"age":55000,"immortal":true,"name":"Gendalf the \"White\"","power":50.0},{"age":50,"immortal":false,"name":"Frodo","power":5.0}]"#,
+        r#"age,immortal,name,power
+55000,true,"Gendalf the ""White""",50.0
+50,false,Frodo,5.0
+"#,
+        true
+    )]
+    #[case(
+        Format::Json,
+        Format::Csv,
+        r#"[{"name":"Gendalf","title":"the Grey, the White"},{"name":"Frodo","age":50,"ring":null}]"#,
+        r#"name,title,age,ring
+Gendalf,"the Grey, the White",,
+Frodo,,50,
+"#,
+        true
+    )]
+    #[case(
+        Format::Json,
+        Format::Csv,
+        r#"[{"name":"Frodo","address":{"city":"Hobbiton","shire":true},"tags":["hobbit","ring-bearer"],"ring":{},"pets":[]}]"#,
+        r#"name,address.city,address.shire,tags.0,tags.1,ring,pets
+Frodo,Hobbiton,true,hobbit,ring-bearer,{},[]
+"#,
+        true
+    )]
+    #[case(
+        Format::Json,
+        Format::Json,
+        r#"[{"age":55000,"immortal":true,"name":"Gendalf the \"White\"","power":50.0},{"age":50,"immortal":false,"name":"Frodo","power":5.0}]"#,
+        r#"[{"age":55000,"immortal":true,"name":"Gendalf the \"White\"","power":50.0},{"age":50,"immortal":false,"name":"Frodo","power":5.0}]"#,
+        true
+    )]
+    #[case(
+        Format::Jsonl,
+        Format::Xml,
+        r#"{"age":55000,"immortal":true,"name":"Gendalf the \"White\"","power":50.0}
+{"age":50,"immortal":false,"name":"Frodo","power":5.0}
+"#,
+        r#"<root>
+  <age>55000</age>
+  <immortal>true</immortal>
+  <name>Gendalf the &quot;White&quot;</name>
+  <power>50.0</power>
+</root>
+<root>
+  <age>50</age>
+  <immortal>false</immortal>
+  <name>Frodo</name>
+  <power>5.0</power>
+</root>
+"#,
+        false
+    )]
+    #[case(
+    Format::Json,
+    Format::Jsonl,
+    r#"[{"age":55000,"immortal":true,"name":"Gendalf the \"White\"","power":50.0},{"age":50,"immortal":false,"name":"Frodo","power":5.0}]"#,
+    r#"{"age":55000,"immortal":true,"name":"Gendalf the \"White\"","power":50.0}
+{"age":50,"immortal":false,"name":"Frodo","power":5.0}
+"#,
+    false
+)]
+    fn test_raw_convert(
+        #[case] from_format: Format,
+        #[case] to_format: Format,
+        #[case] input: &str,
+        #[case] expected_output: &str,
+        #[case] is_compact: bool,
+    ) {
+        let value = load_input(input.as_bytes(), from_format).unwrap();
+        let output = String::from_utf8(dump_value(&value, to_format, is_compact).unwrap()).unwrap();
+
+        assert_eq!(output, expected_output);
+    }
+
+    #[rstest]
+    #[case(
+        Format::Json,
+        false,
+        "",
+        r#"{"kind":"Pod","metadata":{"z":1,"a":2},"apiVersion":"v1"}"#
+    )]
+    #[case(
+        Format::Json,
+        true,
+        "",
+        r#"{"apiVersion":"v1","kind":"Pod","metadata":{"a":2,"z":1}}"#
+    )]
+    #[case(
+        Format::Json,
+        false,
+        "apiVersion,kind",
+        r#"{"apiVersion":"v1","kind":"Pod","metadata":{"z":1,"a":2}}"#
+    )]
+    #[case(
+        Format::Yaml,
+        true,
+        "metadata",
+        r#"{"metadata":{"a":2,"z":1},"apiVersion":"v1","kind":"Pod"}"#
+    )]
+    #[case(
+        Format::Toml,
+        true,
+        "kind",
+        r#"{"kind":"Pod","apiVersion":"v1","metadata":{"a":2,"z":1}}"#
+    )]
+    #[case(
+        Format::Plist,
+        false,
+        "a,apiVersion",
+        r#"{"apiVersion":"v1","kind":"Pod","metadata":{"a":2,"z":1}}"#
+    )]
+    fn test_key_order(
+        #[case] from: Format,
+        #[case] sort: bool,
+        #[case] first: &str,
+        #[case] expected_output: &str,
+    ) {
+        let input = r#"{"kind":"Pod","metadata":{"z":1,"a":2},"apiVersion":"v1"}"#;
+        let value = load_input(input.as_bytes(), Format::Json).unwrap();
+        let input = dump_value(&value, from, false).unwrap();
+        let value = load_input(&input, from).unwrap();
+        let options = DumpOptions {
+            is_compact: true,
+            key_order: KeyOrder {
+                sort,
+                first: first
+                    .split(',')
+                    .filter(|key| !key.is_empty())
+                    .map(String::from)
+                    .collect(),
+            },
+            ..Default::default()
+        };
+        let output = dump_value_with_options(&value, Format::Json, &options).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), expected_output);
+    }
+
+    #[rstest]
+    #[case(
+        Format::Json,
+        Formatting {
+            indent: Some(4),
+            ..Default::default()
+        },
+        "{\n    \"a\": [\n        1,\n        2\n    ],\n    \"b\": {\n        \"c\": true\n    }\n}"
+    )]
+    #[case(
+        Format::Json,
+        Formatting {
+            indent_tabs: true,
+            max_width: Some(12),
+            ..Default::default()
+        },
+        "{\n\t\"a\": [1, 2],\n\t\"b\": {\n\t\t\"c\": true\n\t}\n}"
+    )]
+    #[case(
+        Format::Json,
+        Formatting {
+            line_ending: LineEnding::Crlf,
+            final_newline: true,
+            max_width: Some(80),
+            ..Default::default()
+        },
+        "{ \"a\": [1, 2], \"b\": { \"c\": true } }\r\n"
+    )]
+    #[case(
+        Format::Json,
+        Formatting {
+            max_width: Some(35),
+            ..Default::default()
+        },
+        "{ \"a\": [1, 2], \"b\": { \"c\": true } }"
+    )]
+    #[case(
+        Format::Json,
+        Formatting {
+            max_width: Some(34),
+            ..Default::default()
+        },
+        "{\n  \"a\": [1, 2],\n  \"b\": { \"c\": true }\n}"
+    )]
+    #[case(
+        Format::Json5,
+        Formatting {
+            indent: Some(1),
+            ..Default::default()
+        },
+        "{\n a: [\n  1,\n  2,\n ],\n b: {\n  c: true,\n },\n}"
+    )]
+    #[case(
+        Format::Ron,
+        Formatting {
+            indent: Some(2),
+            ..Default::default()
+        },
+        "{\n  \"a\": [\n    1,\n    2,\n  ],\n  \"b\": {\n    \"c\": true,\n  },\n}"
+    )]
+    #[case(
+        Format::Toml,
+        Formatting {
+            indent: Some(2),
+            ..Default::default()
+        },
+        "a = [\n  1,\n  2,\n]\n\n[b]\nc = true\n"
+    )]
+    #[case(
+        Format::Toml,
+        Formatting {
+            max_width: Some(10),
+            ..Default::default()
+        },
+        "a = [1, 2]\n\n[b]\nc = true\n"
+    )]
+    #[case(
+        Format::Xml,
+        Formatting {
+            indent_tabs: true,
+            line_ending: LineEnding::Crlf,
+            ..Default::default()
+        },
+        "<root>\r\n\t<a>1</a>\r\n\t<a>2</a>\r\n\t<b>\r\n\t\t<c>true</c>\r\n\t</b>\r\n</root>\r\n"
+    )]
+    fn test_formatting(
+        #[case] format: Format,
+        #[case] formatting: Formatting,
+        #[case] expected_output: &str,
+    ) {
+        let value = load_input(r#"{"a":[1,2],"b":{"c":true}}"#.as_bytes(), Format::Json).unwrap();
+        let options = DumpOptions {
+            formatting,
+            ..Default::default()
+        };
+        let output = dump_value_with_options(&value, format, &options).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), expected_output);
+    }
+
+    #[rstest]
+    #[case(Format::Csv, r#"[{"a":"x\ny","b":1}]"#, "a,b\r\n\"x\ny\",1\r\n")]
+    #[case(
+        Format::Sql,
+        r#"[{"a":"x\ny"}]"#,
+        "CREATE TABLE \"data\" (\r\n  \"a\" TEXT\r\n);\r\nINSERT INTO \"data\" (\"a\") VALUES ('x\ny');\r\n"
+    )]
+    #[case(
+        Format::Toml,
+        r#"{"a":"x\ny","b":"'\"","c":[1]}"#,
+        "a = \"\"\"\nx\ny\"\"\"\r\nb = \"\"\"'\"\"\"\"\r\nc = [1]\r\n"
+    )]
+    #[case(Format::Json, r#"{"a":"x\ny"}"#, "{\r\n  \"a\": \"x\\ny\"\r\n}\r\n")]
+    fn test_line_ending_keeps_values(
+        #[case] format: Format,
+        #[case] input: &str,
+        #[case] expected_output: &str,
+    ) {
+        let value = load_input(input.as_bytes(), Format::Json).unwrap();
+        let options = DumpOptions {
+            formatting: Formatting {
+                line_ending: LineEnding::Crlf,
+                final_newline: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let output = dump_value_with_options(&value, format, &options).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), expected_output);
+    }
+
+    #[rstest]
+    #[case(
+        Format::Json,
+        Format::Yaml,
+        r#"{"name":"Frodo, Baggins","lines":"a\nb","empty":{},"rings":[]}"#,
+        "{name: \"Frodo, Baggins\", lines: \"a\\nb\", empty: {}, rings: []}\n"
+    )]
+    #[case(
+        Format::Yaml,
+        Format::Yaml,
+        "ring: !One [1, 2]\n'1': '1'\n",
+        "{ring: !One [1, 2], '1': '1'}\n"
+    )]
+    #[case(
+        Format::Json,
+        Format::Json5,
+        r#"{"a":[],"b":[{"c":"x, y"}]}"#,
+        r#"{a: [], b: [{c: "x, y"}]}"#
+    )]
+    fn test_compact(
+        #[case] from: Format,
+        #[case] to: Format,
+        #[case] input: &str,
+        #[case] expected_output: &str,
+    ) {
+        let value = load_input(input.as_bytes(), from).unwrap();
+        let output = dump_value(&value, to, true).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert_eq!(output, expected_output);
+        // compact output loads like pretty output
+        let pretty = dump_value(&value, to, false).unwrap();
+        let to_json = |input: &[u8]| {
+            let value = load_input(input, to).unwrap();
+            dump_value(&value, Format::Json, true).unwrap()
+        };
+        assert_eq!(to_json(output.as_bytes()), to_json(&pretty));
+    }
+
+    #[test]
+    fn test_compact_output() {
+        assert_eq!(Format::Yaml.compact_output(), CompactOutput::Optional);
+        assert_eq!(Format::Csv.compact_output(), CompactOutput::Always);
+        assert_eq!(Format::Toon.compact_output(), CompactOutput::Unsupported);
+    }
+
+    #[test]
+    fn test_csv_sorted_columns() {
+        let value = load_input(
+            r#"[{"name":"Frodo","age":50},{"power":5.0,"name":"Sam"}]"#.as_bytes(),
+            Format::Json,
+        )
+        .unwrap();
+        let options = DumpOptions {
+            csv: CsvDumpOptions {
+                column_order: ColumnOrder::Sorted,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let output = dump_value_with_options(&value, Format::Csv, &options).unwrap();
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            "age,name,power\n50,Frodo,\n,Sam,5.0\n"
+        );
+    }
+
+    #[rstest]
+    #[case(
+        false,
+        ArrayStyle::Indexed,
+        r#"name,address,tags
+Frodo,"{""city"":""Hobbiton""}","[""hobbit"",""ring-bearer""]"
+"#
+    )]
+    #[case(
+        true,
+        ArrayStyle::Joined,
+        r#"name,address.city,tags
+Frodo,Hobbiton,hobbit;ring-bearer
+"#
+    )]
+    fn test_csv_nested(
+        #[case] flatten: bool,
+        #[case] array_style: ArrayStyle,
+        #[case] expected_output: &str,
+    ) {
+        let value = load_input(
+            r#"[{"name":"Frodo","address":{"city":"Hobbiton"},"tags":["hobbit","ring-bearer"]}]"#
+                .as_bytes(),
+            Format::Json,
+        )
+        .unwrap();
+        let options = DumpOptions {
+            csv: CsvDumpOptions {
+                flatten,
+                array_style,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let output = dump_value_with_options(&value, Format::Csv, &options).unwrap();
+
+        assert_eq!(String::from_utf8(output).unwrap(), expected_output);
+    }
+
+    #[test]
+    fn test_csv_unflatten() {
+        let options = LoadOptions {
+            csv: CsvLoadOptions {
+                unflatten: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let value = load_input_with_options(
+            "name,address.city,tags.0,tags.1,friends.0.name\nFrodo,Hobbiton,hobbit,,Sam\n"
+                .as_bytes(),
+            Format::Csv,
+            &options,
+        )
+        .unwrap();
+        let output = dump_value(&value, Format::Json, true).unwrap();
+
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            r#"[{"name":"Frodo","address":{"city":"Hobbiton"},"tags":["hobbit",null],"friends":[{"name":"Sam"}]}]"#
+        );
+
+        let input = r#"[{"name":"Frodo","tags":[],"address":{},"rings":[{}]}]"#;
+        let value = load_input(input.as_bytes(), Format::Json).unwrap();
+        let csv = dump_value(&value, Format::Csv, false).unwrap();
+        assert_eq!(
+            String::from_utf8(csv.clone()).unwrap(),
+            "name,tags,address,rings.0\nFrodo,[],{},{}\n"
+        );
+        let value = load_input_with_options(&csv, Format::Csv, &options).unwrap();
+        let output = dump_value(&value, Format::Json, true).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), input);
+
+        let conflicting = load_input_with_options(
+            "address,address.city\nBag End,Hobbiton\n".as_bytes(),
+            Format::Csv,
+            &options,
+        );
+        assert!(conflicting.is_err());
+    }
+
+    #[test]
+    fn test_csv_flatten_errors() {
+        let value = load_input(r#"[{"a.b":1,"a":{"b":2}}]"#.as_bytes(), Format::Json).unwrap();
+        let err = dump_value(&value, Format::Csv, false).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            r#"Csv column "a.b" is flattened from more than one key"#
+        );
+
+        let options = LoadOptions {
+            csv: CsvLoadOptions {
+                unflatten: true,
+                key_separator: String::new(),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        assert!(load_input_with_options("ab\n1\n".as_bytes(), Format::Csv, &options).is_err());
+    }
+
+    #[rstest]
+    #[case(
+        true,
+        "",
+        TypeMismatch::Error,
+        r#"[{"zip":1234,"price":5,"active":true,"created":"2024-01-02T03:04:05Z"}]"#
+    )]
+    #[case(
+        false,
+        "",
+        TypeMismatch::Error,
+        r#"[{"zip":"01234","price":"5","active":"true","created":"2024-01-02T03:04:05Z"}]"#
+    )]
+    #[case(
+        true,
+        "zip:string,price:float,created:datetime",
+        TypeMismatch::Error,
+        r#"[{"zip":"01234","price":5.0,"active":true,"created":"2024-01-02T03:04:05Z"}]"#
+    )]
+    #[case(
+        true,
+        "zip:bool,created:int",
+        TypeMismatch::Null,
+        r#"[{"zip":null,"price":5,"active":true,"created":null}]"#
+    )]
+    #[case(
+        true,
+        "zip:bool",
+        TypeMismatch::String,
+        r#"[{"zip":"01234","price":5,"active":true,"created":"2024-01-02T03:04:05Z"}]"#
+    )]
+    fn test_csv_types(
+        #[case] infer_types: bool,
+        #[case] types: &str,
+        #[case] type_mismatch: TypeMismatch,
+        #[case] expected_output: &str,
+    ) {
+        let options = LoadOptions {
+            csv: CsvLoadOptions {
+                infer_types,
+                column_types: parse_column_types(types).unwrap(),
+                type_mismatch,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let value = load_input_with_options(
+            "zip,price,active,created\n01234,5,true,2024-01-02T03:04:05Z\n".as_bytes(),
+            Format::Csv,
+            &options,
+        )
+        .unwrap();
+        let output = dump_value(&value, Format::Json, true).unwrap();
+
+        assert_eq!(String::from_utf8(output).unwrap(), expected_output);
+    }
+
+    #[rstest]
+    #[case("price:float", "price\n5\nfree\n")]
+    #[case("created:datetime", "created\nyesterday\n")]
+    #[case("missing:int", "price\n5\n")]
+    #[case("id:float", "id\n9007199254740993\n")]
+    #[case("id:float", "id\n123456789012345678901234567890123456789012\n")]
+    fn test_csv_types_errors(#[case] types: &str, #[case] input: &str) {
+        let options = LoadOptions {
+            csv: CsvLoadOptions {
+                column_types: parse_column_types(types).unwrap(),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        assert!(load_input_with_options(input.as_bytes(), Format::Csv, &options).is_err());
+    }
+
+    #[test]
+    fn test_parse_column_types() {
+        let types = parse_column_types("# schema\nid: int\nprice:number, name:str\n").unwrap();
+        assert_eq!(types.len(), 3);
+        assert_eq!(types["id"], ColumnType::Int);
+        assert_eq!(types["price"], ColumnType::Float);
+        assert_eq!(types["name"], ColumnType::String);
+
+        assert!(parse_column_types("id").is_err());
+        assert!(parse_column_types("id:uuid").is_err());
+    }
+
+    #[cfg(feature = "spreadsheet")]
+    #[test]
+    fn test_convert_xlsx() {
+        let input = r#"[{"id":1,"name":"Frodo","power":5.5,"immortal":false,"ring":null},{"id":2,"name":"Sam","address":{"city":"Hobbiton"}}]"#;
+        let value = load_input(input.as_bytes(), Format::Json).unwrap();
+        let options = DumpOptions {
+            sheet: Some("hobbits".to_string()),
+            ..Default::default()
+        };
+        let xlsx = dump_value_with_options(&value, Format::Xlsx, &options).unwrap();
+
+        for sheet in [None, Some("hobbits"), Some("0")] {
+            let options = LoadOptions {
+                sheet: sheet.map(str::to_string),
+                ..Default::default()
+            };
+            let value = load_input_with_options(&xlsx, Format::Xlsx, &options).unwrap();
+            let output = dump_value(&value, Format::Json, true).unwrap();
+            assert_eq!(
+                String::from_utf8(output).unwrap(),
+                r#"[{"id":1,"name":"Frodo","power":5.5,"immortal":false,"ring":null,"address.city":null},{"id":2,"name":"Sam","power":null,"immortal":null,"ring":null,"address.city":"Hobbiton"}]"#
+            );
+        }
+
+        let options = LoadOptions {
+            sheet: Some("elves".to_string()),
+            ..Default::default()
+        };
+        assert!(load_input_with_options(&xlsx, Format::Xlsx, &options).is_err());
+        assert!(dump_value(&value, Format::Ods, false).is_err());
+        assert!(!Format::Ods.is_output());
+    }
+
+    #[cfg(feature = "spreadsheet")]
+    #[test]
+    fn test_load_xlsx_number_formats_and_headers() {
+        use rust_xlsxwriter::{Format as CellFormat, Workbook};
+
+        let mut workbook = Workbook::new();
+        let worksheet = workbook.add_worksheet();
+        for (col, key) in ["n", "n", "", "n_2"].into_iter().enumerate() {
+            worksheet.write_string(0, col as u16, key).unwrap();
+        }
+        let formats = [
+            CellFormat::new(),
+            CellFormat::new().set_num_format("0"),
+            CellFormat::new().set_num_format("#,##0 \"pcs\""),
+            CellFormat::new().set_num_format("0.00"),
+        ];
+        for (col, format) in formats.iter().enumerate() {
+            worksheet
+                .write_number_with_format(1, col as u16, 1.0, format)
+                .unwrap();
+        }
+        let xlsx = workbook.save_to_buffer().unwrap();
+
+        let value = load_input(&xlsx, Format::Xlsx).unwrap();
+        let output = dump_value(&value, Format::Json, true).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            r#"[{"n":1.0,"n_2":1,"C":1,"n_2_2":1.0}]"#
+        );
+
+        let input = r#"[{"count":1,"weight":1.0}]"#;
+        let value = load_input(input.as_bytes(), Format::Json).unwrap();
+        let xlsx = dump_value(&value, Format::Xlsx, false).unwrap();
+        let value = load_input(&xlsx, Format::Xlsx).unwrap();
+        let output = dump_value(&value, Format::Json, true).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), input);
+    }
+
+    #[rstest]
+    #[case(
+        r#"[{"name":"Gendalf | the Grey","age":55000,"address":{"city":"Valinor"}},{"name":"Frodo","age":50}]"#,
+        r#"| name                |   age | address.city |
+| ------------------- | ----: | ------------ |
+| Gendalf \| the Grey | 55000 | Valinor      |
+| Frodo               |    50 |              |
+"#
+    )]
+    #[case(
+        r#"{"name":"Frodo","friends":["Sam",{"name":"Pippin","age":28}],"ring":null,"items":[]}"#,
+        r#"- **name**: Frodo
+- **friends**:
+  - Sam
+  -
+    - **name**: Pippin
+    - **age**: 28
+- **ring**: null
+- **items**: []
+"#
+    )]
+    #[case(r#""Frodo""#, "Frodo\n")]
+    #[case(
+        r#"{"ring_bearer*":"Frodo | *Sam*","tags":["[one]"]}"#,
+        "- **ring\\_bearer\\***: Frodo \\| \\*Sam\\*\n- **tags**:\n  - \\[one\\]\n"
+    )]
+    #[case(
+        r#"[{"name":"_Frodo_","power":1}]"#,
+        "| name      | power |\n| --------- | ----: |\n| \\_Frodo\\_ |     1 |\n"
+    )]
+    #[case(
+        r#"[{"name":"フロド","race":"hobbit"},{"name":"Sam 🍲","race":"hobbit"}]"#,
+        "| name   | race   |\n| ------ | ------ |\n| フロド | hobbit |\n| Sam 🍲 | hobbit |\n"
+    )]
+    fn test_convert_markdown(#[case] input: &str, #[case] expected_output: &str) {
+        let value = load_input(input.as_bytes(), Format::Json).unwrap();
+        let output = dump_value(&value, Format::Markdown, false).unwrap();
+
+        assert_eq!(String::from_utf8(output).unwrap(), expected_output);
+        assert!(load_input(expected_output.as_bytes(), Format::Markdown).is_err());
+        assert!(!Format::Markdown.is_input());
+    }
+
+    #[rstest]
+    #[case(
+        r#"[{"name":"Frodo & Sam","age":50},{"name":"Pippin"}]"#,
+        true,
+        r#"<table><thead><tr><th>name</th><th>age</th></tr></thead><tbody><tr><td>Frodo &amp; Sam</td><td>50</td></tr><tr><td>Pippin</td><td></td></tr></tbody></table>"#
+    )]
+    #[case(
+        r#"{"name":"<Frodo>","friends":["Sam",{"name":"Pippin"}],"ring":null}"#,
+        false,
+        r#"<dl>
+  <dt>name</dt>
+  <dd>&lt;Frodo&gt;</dd>
+  <dt>friends</dt>
+  <dd>
+    <ul>
+      <li>Sam</li>
+      <li>
+        <dl>
+          <dt>name</dt>
+          <dd>Pippin</dd>
+        </dl>
+      </li>
+    </ul>
+  </dd>
+  <dt>ring</dt>
+  <dd>null</dd>
+</dl>
+"#
+    )]
+    fn test_convert_html_fragment(
+        #[case] input: &str,
+        #[case] is_compact: bool,
+        #[case] expected_output: &str,
+    ) {
+        let value = load_input(input.as_bytes(), Format::Json).unwrap();
+        let options = DumpOptions {
+            is_compact,
+            html: HtmlDumpOptions {
+                fragment: true,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let output = dump_value_with_options(&value, Format::Html, &options).unwrap();
+
+        assert_eq!(String::from_utf8(output).unwrap(), expected_output);
+    }
+
+    #[test]
+    fn test_convert_html_page() {
+        let value = load_input(r#""Frodo""#.as_bytes(), Format::Json).unwrap();
+        let options = DumpOptions {
+            html: HtmlDumpOptions {
+                title: Some("Hobbits & co".to_string()),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let output = dump_value_with_options(&value, Format::Html, &options).unwrap();
+        let output = String::from_utf8(output).unwrap();
+
+        assert!(output.starts_with("<!DOCTYPE html>\n<html>\n"));
+        assert!(output.contains("<title>Hobbits &amp; co</title>"));
+        assert!(output.ends_with("<body>\n    Frodo\n  </body>\n</html>\n"));
+        assert!(load_input(output.as_bytes(), Format::Html).is_err());
+        assert!(!Format::Html.is_input());
+    }
+
+    #[rstest]
+    #[case(
+        SqlDialect::Sqlite,
+        false,
+        r#"CREATE TABLE "hob""bits" (
+  "id" INTEGER,
+  "name" TEXT,
+  "ring" BOOLEAN,
+  "power" REAL,
+  "age" TEXT
+);
+INSERT INTO "hob""bits" ("id", "name", "ring", "power", "age") VALUES (1, 'Frodo O''Baggins \', 1, 5, '50');
+INSERT INTO "hob""bits" ("id", "name", "ring", "power", "age") VALUES (2, NULL, 0, 2.5, 33);
+"#
+    )]
+    #[case(
+        SqlDialect::Postgres,
+        true,
+        r#"CREATE TABLE "hob""bits" ("id" BIGINT, "name" TEXT, "ring" BOOLEAN, "power" DOUBLE PRECISION, "age" TEXT);
+INSERT INTO "hob""bits" ("id", "name", "ring", "power", "age") VALUES (1, 'Frodo O''Baggins \', TRUE, 5, '50');
+INSERT INTO "hob""bits" ("id", "name", "ring", "power", "age") VALUES (2, NULL, FALSE, 2.5, 33);
+"#
+    )]
+    #[case(
+        SqlDialect::Mysql,
+        true,
+        r#"CREATE TABLE `hob"bits` (`id` BIGINT, `name` TEXT, `ring` BOOLEAN, `power` DOUBLE, `age` TEXT);
+INSERT INTO `hob"bits` (`id`, `name`, `ring`, `power`, `age`) VALUES (1, 'Frodo O''Baggins \\', TRUE, 5, '50');
+INSERT INTO `hob"bits` (`id`, `name`, `ring`, `power`, `age`) VALUES (2, NULL, FALSE, 2.5, 33);
+"#
+    )]
+    fn test_convert_sql(
+        #[case] dialect: SqlDialect,
+        #[case] is_compact: bool,
+        #[case] expected_output: &str,
+    ) {
+        let input = r#"[{"id":1,"name":"Frodo O'Baggins \\","ring":true,"power":5,"age":"50"},{"id":2,"ring":false,"power":2.5,"age":33}]"#;
+        let value = load_input(input.as_bytes(), Format::Json).unwrap();
+        let options = DumpOptions {
+            is_compact,
+            sql: SqlDumpOptions {
+                dialect,
+                table: "hob\"bits".to_string(),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let output = dump_value_with_options(&value, Format::Sql, &options).unwrap();
+
+        assert_eq!(String::from_utf8(output).unwrap(), expected_output);
+    }
+
+    #[rstest]
+    #[case("[]")]
+    #[case("[{}, {}]")]
+    fn test_convert_sql_without_columns(#[case] input: &str) {
+        let value = load_input(input.as_bytes(), Format::Json).unwrap();
+        let err = dump_value(&value, Format::Sql, false).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Sql table needs columns, input has no keys"
+        );
+        assert!(!Format::Sql.is_input());
+    }
+
+    #[rstest]
+    #[case(
+        Format::Sql,
+        r#"[{"id":9223372036854775807},{"id":9223372036854775808}]"#
+    )]
+    #[cfg_attr(
+        feature = "sqlite",
+        case(Format::Sqlite, r#"[{"id":-9223372036854775809}]"#)
+    )]
+    fn test_convert_sql_integer_overflow(#[case] to: Format, #[case] input: &str) {
+        let value = load_input(input.as_bytes(), Format::Json).unwrap();
+        assert!(dump_value(&value, to, false).is_err());
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[rstest]
+    #[case("[]")]
+    #[case("[{}]")]
+    fn test_convert_sqlite_without_columns(#[case] input: &str) {
+        let value = load_input(input.as_bytes(), Format::Json).unwrap();
+        let err = dump_value(&value, Format::Sqlite, false).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Sqlite table needs columns, input has no keys"
+        );
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn test_convert_sqlite() {
+        let input = r#"[{"id":1,"name":"Frodo","ring":true,"power":5.5},{"id":2,"name":"Sam","address":{"city":"Hobbiton"}}]"#;
+        let value = load_input(input.as_bytes(), Format::Json).unwrap();
+        let options = DumpOptions {
+            sql: SqlDumpOptions {
+                table: "hobbits".to_string(),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let db = dump_value_with_options(&value, Format::Sqlite, &options).unwrap();
+
+        let value = load_input(&db, Format::Sqlite).unwrap();
+        let output = dump_value(&value, Format::Json, true).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            r#"[{"id":1,"name":"Frodo","ring":1,"power":5.5,"address.city":null},{"id":2,"name":"Sam","ring":null,"power":null,"address.city":"Hobbiton"}]"#
+        );
+
+        let options = LoadOptions {
+            sqlite: SqliteLoadOptions {
+                query: Some(
+                    r#"SELECT name FROM hobbits WHERE "address.city" = 'Hobbiton'"#.to_string(),
+                ),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let value = load_input_with_options(&db, Format::Sqlite, &options).unwrap();
+        let output = dump_value(&value, Format::Json, true).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), r#"[{"name":"Sam"}]"#);
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn test_load_sqlite_table() {
+        let connection = rusqlite::Connection::open_in_memory().unwrap();
+        connection
+            .execute_batch(
+                "CREATE TABLE rings (name TEXT, inscription BLOB);
+                INSERT INTO rings VALUES ('One', x'4173682e');
+                CREATE TABLE hobbits (name TEXT);",
+            )
+            .unwrap();
+        let db = connection.serialize(rusqlite::MAIN_DB).unwrap().to_vec();
+
+        assert!(load_input(&db, Format::Sqlite).is_err());
+
+        let options = LoadOptions {
+            sqlite: SqliteLoadOptions {
+                table: Some("rings".to_string()),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let value = load_input_with_options(&db, Format::Sqlite, &options).unwrap();
+        let output = dump_value(&value, Format::Json, true).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            r#"[{"name":"One","inscription":"QXNoLg=="}]"#
+        );
+    }
+
+    #[cfg(feature = "sqlite")]
+    #[test]
+    fn test_load_sqlite_infinity() {
+        let connection = rusqlite::Connection::open_in_memory().unwrap();
+        connection
+            .execute_batch(
+                "CREATE TABLE rings (power REAL); INSERT INTO rings VALUES (1.5), (9e999);",
+            )
+            .unwrap();
+        let db = connection.serialize(rusqlite::MAIN_DB).unwrap().to_vec();
+
+        let err = load_input(&db, Format::Sqlite).unwrap_err();
+        assert_eq!(err.to_string(), "Column power of row 1 is invalid");
+    }
+
+    #[rstest]
+    #[case(
+        XmlNamespaces::Keep,
+        r##"{"soap:Envelope":{"@xmlns:soap":"http://schemas.xmlsoap.org/soap/envelope/","@xmlns":"urn:shire","soap:Body":{"hobbit":{"@soap:role":"bearer","#text":"Frodo & Sam"},"ring":null}}}"##
+    )]
+    #[case(
+        XmlNamespaces::Expand,
+        r##"{"{http://schemas.xmlsoap.org/soap/envelope/}Envelope":{"{http://schemas.xmlsoap.org/soap/envelope/}Body":{"{urn:shire}hobbit":{"@{http://schemas.xmlsoap.org/soap/envelope/}role":"bearer","#text":"Frodo & Sam"},"{urn:shire}ring":null}}}"##
+    )]
+    #[case(
+        XmlNamespaces::Strip,
+        r##"{"Envelope":{"Body":{"hobbit":{"@role":"bearer","#text":"Frodo & Sam"},"ring":null}}}"##
+    )]
+    fn test_load_xml_namespaces(#[case] namespaces: XmlNamespaces, #[case] expected_output: &str) {
+        let input = r#"<soap:Envelope xmlns:soap="http://schemas.xmlsoap.org/soap/envelope/" xmlns="urn:shire">
+  <soap:Body><hobbit soap:role="bearer">Frodo &amp; Sam</hobbit><ring/></soap:Body>
+</soap:Envelope>"#;
+        let options = LoadOptions {
+            xml: XmlLoadOptions {
+                namespaces,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let value = load_input_with_options(input.as_bytes(), Format::Xml, &options).unwrap();
+        let output = dump_value(&value, Format::Json, true).unwrap();
+
+        assert_eq!(String::from_utf8(output).unwrap(), expected_output);
+    }
+
+    #[rstest]
+    #[case(XmlNamespaces::Keep, vec![])]
+    #[case(
+        XmlNamespaces::Expand,
+        vec![
+            ("soap".to_string(), "http://schemas.xmlsoap.org/soap/envelope/".to_string()),
+            ("".to_string(), "urn:shire".to_string()),
+        ]
+    )]
+    fn test_xml_namespaces_roundtrip(
+        #[case] load_namespaces: XmlNamespaces,
+        #[case] dump_namespaces: Vec<(String, String)>,
+    ) {
+        let input = r#"<soap:Envelope xmlns:soap="http://schemas.xmlsoap.org/soap/envelope/" xmlns="urn:shire"><soap:Body><hobbit soap:role="bearer">Frodo &amp; Sam</hobbit><ring/></soap:Body></soap:Envelope>"#;
+        let options = LoadOptions {
+            xml: XmlLoadOptions {
+                namespaces: load_namespaces,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let value = load_input_with_options(input.as_bytes(), Format::Xml, &options).unwrap();
+        let json = dump_value(&value, Format::Json, true).unwrap();
+        let value = load_input(&json, Format::Json).unwrap();
+        let options = DumpOptions {
+            is_compact: true,
+            xml: XmlDumpOptions {
+                namespaces: dump_namespaces,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let output = dump_value_with_options(&value, Format::Xml, &options).unwrap();
+
+        assert_eq!(String::from_utf8(output).unwrap(), input);
+    }
+
+    #[rstest]
+    #[case(
+        vec![("".to_string(), "urn:shire".to_string()), ("s".to_string(), "urn:soap".to_string())],
+        r#"<s:Body xmlns="urn:shire" xmlns:s="urn:soap"><hobbit s:role="bearer">Frodo</hobbit><plain/></s:Body>"#
+    )]
+    #[case(
+        vec![],
+        r#"<ns0:Body xmlns:ns0="urn:soap" xmlns:ns1="urn:shire"><ns1:hobbit ns0:role="bearer">Frodo</ns1:hobbit><plain/></ns0:Body>"#
+    )]
+    fn test_dump_xml_namespaces(
+        #[case] namespaces: Vec<(String, String)>,
+        #[case] expected_output: &str,
+    ) {
+        let input = r##"{"{urn:soap}Body":{"{urn:shire}hobbit":{"@{urn:soap}role":"bearer","#text":"Frodo"},"plain":null}}"##;
+        let value = load_input(input.as_bytes(), Format::Json).unwrap();
+        let options = DumpOptions {
+            is_compact: true,
+            xml: XmlDumpOptions {
+                namespaces,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let output = dump_value_with_options(&value, Format::Xml, &options).unwrap();
+
+        assert_eq!(String::from_utf8(output).unwrap(), expected_output);
+    }
+
+    #[rstest]
+    #[case(
+        XmlConvention::Default,
+        r##"{"shire":{"@name":"Hobbiton","hobbit":[{"@age":50,"#text":"Frodo"}],"ring":null}}"##,
+        r#"<shire name="Hobbiton"><hobbit age="50">Frodo</hobbit><ring/></shire>"#
+    )]
+    #[case(
+        XmlConvention::Badgerfish,
+        r#"{"shire":{"@name":"Hobbiton","hobbit":[{"@age":50,"$":"Frodo"}],"ring":{}}}"#,
+        r#"<shire name="Hobbiton"><hobbit age="50">Frodo</hobbit><ring/></shire>"#
+    )]
+    #[case(
+        XmlConvention::Parker,
+        r#"{"hobbit":["Frodo"],"ring":null}"#,
+        r#"<root><hobbit>Frodo</hobbit><ring/></root>"#
+    )]
+    #[case(
+        XmlConvention::Gdata,
+        r#"{"shire":{"name":"Hobbiton","hobbit":[{"age":50,"$t":"Frodo"}],"ring":{}}}"#,
+        r#"<shire name="Hobbiton"><hobbit age="50">Frodo</hobbit><ring/></shire>"#
+    )]
+    fn test_convert_xml_conventions(
+        #[case] convention: XmlConvention,
+        #[case] expected_json: &str,
+        #[case] expected_xml: &str,
+    ) {
+        let input = r#"<shire name="Hobbiton"><hobbit age="50">Frodo</hobbit><ring/></shire>"#;
+        let mapping = XmlMapping {
+            convention,
+            force_arrays: vec!["hobbit".to_string()],
+            ..Default::default()
+        };
+        let options = LoadOptions {
+            xml: XmlLoadOptions {
+                mapping: mapping.clone(),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let value = load_input_with_options(input.as_bytes(), Format::Xml, &options).unwrap();
+        let output = dump_value(&value, Format::Json, true).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), expected_json);
+
+        let options = DumpOptions {
+            is_compact: true,
+            xml: XmlDumpOptions {
+                mapping,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let output = dump_value_with_options(&value, Format::Xml, &options).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), expected_xml);
+    }
+
+    #[test]
+    fn test_convert_xml_custom_keys() {
+        let mapping = XmlMapping {
+            attr_prefix: Some("-".to_string()),
+            text_key: Some("value".to_string()),
+            ..Default::default()
+        };
+        let options = LoadOptions {
+            xml: XmlLoadOptions {
+                mapping: mapping.clone(),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let input = r#"<ring owner="Frodo">One</ring>"#;
+        let value = load_input_with_options(input.as_bytes(), Format::Xml, &options).unwrap();
+        let output = dump_value(&value, Format::Json, true).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            r#"{"ring":{"-owner":"Frodo","value":"One"}}"#
+        );
+
+        let options = DumpOptions {
+            is_compact: true,
+            xml: XmlDumpOptions {
+                mapping,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let output = dump_value_with_options(&value, Format::Xml, &options).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            r#"<ring owner="Frodo">One</ring>"#
+        );
+    }
+
+    #[rstest]
+    #[case(
+        XmlDumpOptions {
+            root: Some("root".to_string()),
+            ..Default::default()
+        },
+        r#"<root>
+  <shire>
+    <hobbit>
+      <name>Frodo</name>
+      <ring/>
+      <title></title>
+    </hobbit>
+    <hobbit>
+      <name>Sam</name>
+    </hobbit>
+  </shire>
+</root>
+"#
+    )]
+    #[case(
+        XmlDumpOptions {
+            root: Some("shire".to_string()),
+            item_name: Some("hobbit".to_string()),
+            declaration: true,
+            self_closing: false,
+            ..Default::default()
+        },
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<shire>
+  <hobbit>
+    <name>Frodo</name>
+    <ring></ring>
+    <title></title>
+  </hobbit>
+  <hobbit>
+    <name>Sam</name>
+  </hobbit>
+</shire>
+"#
+    )]
+    fn test_dump_xml_pretty(#[case] xml: XmlDumpOptions, #[case] expected_output: &str) {
+        let input = match xml.item_name {
+            Some(_) => r#"[{"name":"Frodo","ring":null,"title":""},{"name":"Sam"}]"#,
+            None => {
+                r#"{"shire":{"hobbit":[{"name":"Frodo","ring":null,"title":""},{"name":"Sam"}]}}"#
+            }
+        };
+        let value = load_input(input.as_bytes(), Format::Json).unwrap();
+        let options = DumpOptions {
+            xml,
+            ..Default::default()
+        };
+        let output = dump_value_with_options(&value, Format::Xml, &options).unwrap();
+
+        assert_eq!(String::from_utf8(output).unwrap(), expected_output);
+    }
+
+    #[test]
+    fn test_convert_xml_lossless() {
+        let input = r#"<?xml version="1.0"?>
+<!-- hobbits -->
+<p class="intro">Hello <b>Frodo</b> &amp; <i>Sam</i>!<![CDATA[<ring>]]><?page break?></p>
+"#;
+        let mapping = XmlMapping {
+            convention: XmlConvention::Lossless,
+            ..Default::default()
+        };
+        let options = LoadOptions {
+            xml: XmlLoadOptions {
+                mapping: mapping.clone(),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let value = load_input_with_options(input.as_bytes(), Format::Xml, &options).unwrap();
+        let output = dump_value(&value, Format::Json, true).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            r#"[{"pi":"xml version=\"1.0\""},"\n",{"comment":" hobbits "},"\n",{"name":"p","attributes":{"class":"intro"},"children":["Hello ",{"name":"b","children":["Frodo"]}," & ",{"name":"i","children":["Sam"]},"!",{"cdata":"<ring>"},{"pi":"page break"}]},"\n"]"#
+        );
+
+        let options = DumpOptions {
+            xml: XmlDumpOptions {
+                mapping,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let output = dump_value_with_options(&value, Format::Xml, &options).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), input);
+
+        let value = load_input(r#"[{"element":"p"}]"#.as_bytes(), Format::Json).unwrap();
+        assert!(dump_value_with_options(&value, Format::Xml, &options).is_err());
+    }
+
+    #[rstest]
+    #[case(
+        true,
+        r##"{"ring":{"@id":7,"@power":2.5,"@one":true,"@owner":null,"#text":"Precious"}}"##,
+        r#"<ring id="7" power="2.5" one="true" owner="">Precious</ring>"#
+    )]
+    #[case(
+        false,
+        r##"{"ring":{"@id":"007","@power":"2.50","@one":"true","@owner":"","#text":"Precious"}}"##,
+        r#"<ring id="007" power="2.50" one="true" owner="">Precious</ring>"#
+    )]
+    fn test_convert_xml_attributes(
+        #[case] infer_types: bool,
+        #[case] expected_json: &str,
+        #[case] expected_xml: &str,
+    ) {
+        let input = r#"<ring id="007" power="2.50" one="true" owner="">Precious</ring>"#;
+        let options = LoadOptions {
+            xml: XmlLoadOptions {
+                infer_types,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let value = load_input_with_options(input.as_bytes(), Format::Xml, &options).unwrap();
+        let output = dump_value(&value, Format::Json, true).unwrap();
+        assert_eq!(String::from_utf8(output).unwrap(), expected_json);
+
+        let output = dump_value(&value, Format::Xml, true).unwrap();
+        let output = String::from_utf8(output).unwrap();
+        assert_eq!(output, expected_xml);
+    }
+
+    #[test]
+    fn test_load_xml_schema() {
+        let xsd = r#"<xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema">
+  <xs:simpleType name="Ages"><xs:list itemType="xs:int"/></xs:simpleType>
+  <xs:complexType name="Power">
+    <xs:simpleContent>
+      <xs:extension base="xs:decimal"><xs:attribute name="unit" type="xs:string"/></xs:extension>
+    </xs:simpleContent>
+  </xs:complexType>
+  <xs:element name="shire">
+    <xs:complexType>
+      <xs:sequence maxOccurs="unbounded">
+        <xs:element name="hobbit">
+          <xs:complexType>
+            <xs:sequence>
+              <xs:element name="name" type="xs:string"/>
+              <xs:element name="ring" type="xs:boolean"/>
+              <xs:element name="power" type="Power"/>
+              <xs:element name="ages" type="Ages"/>
+              <xs:element name="born" type="xs:date"/>
+            </xs:sequence>
+            <xs:attribute name="id" type="xs:string"/>
+          </xs:complexType>
+        </xs:element>
+      </xs:sequence>
+    </xs:complexType>
+  </xs:element>
+</xs:schema>"#;
+        let options = LoadOptions {
+            xml: XmlLoadOptions {
+                schema: Some(XmlSchema::parse(xsd.as_bytes()).unwrap()),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let input = r#"<shire><hobbit id="007"><name>1234</name><ring>1</ring><power unit="W">5.0</power><ages>33 50</ages><born>2968-09-22</born></hobbit></shire>"#;
+        let value = load_input_with_options(input.as_bytes(), Format::Xml, &options).unwrap();
+        let output = dump_value(&value, Format::Json, true).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            r##"{"shire":{"hobbit":[{"@id":"007","name":"1234","ring":true,"power":{"@unit":"W","#text":5.0},"ages":[33,50],"born":"2968-09-22"}]}}"##
+        );
+
+        let input = r#"<shire><hobbit><ring>maybe</ring></hobbit></shire>"#;
+        assert!(load_input_with_options(input.as_bytes(), Format::Xml, &options).is_err());
+        assert!(XmlSchema::parse(b"<shire/>").is_err());
+    }
+
+    #[rstest]
+    #[case("xs:date", "2002-09-24Z", Some(r#""2002-09-24Z""#))]
+    #[case("xs:date", "2002-09-24+06:00", Some(r#""2002-09-24+06:00""#))]
+    #[case("xs:time", "13:20:00-05:00", Some(r#""13:20:00-05:00""#))]
+    #[case(
+        "xs:dateTime",
+        "2002-09-24T13:20:00Z",
+        Some(r#""2002-09-24T13:20:00Z""#)
+    )]
+    #[case("xs:date", "2002-09-24+6", None)]
+    #[case("xs:time", "noon", None)]
+    #[case("xs:double", "-INF", Some(r#""-INF""#))]
+    #[case("xs:float", "NaN", Some(r#""NaN""#))]
+    #[case("xs:double", "1e3", Some("1000.0"))]
+    #[case("xs:double", "Infinity", None)]
+    fn test_load_xml_schema_types(
+        #[case] xsd_type: &str,
+        #[case] text: &str,
+        #[case] expected_output: Option<&str>,
+    ) {
+        let xsd = format!(
+            r#"<xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema"><xs:element name="a" type="{xsd_type}"/></xs:schema>"#
+        );
+        let options = LoadOptions {
+            xml: XmlLoadOptions {
+                schema: Some(XmlSchema::parse(xsd.as_bytes()).unwrap()),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let input = format!("<a>{text}</a>");
+        let value = load_input_with_options(input.as_bytes(), Format::Xml, &options);
+        match expected_output {
+            Some(expected) => {
+                let output = dump_value(&value.unwrap(), Format::Json, true).unwrap();
+                assert_eq!(
+                    String::from_utf8(output).unwrap(),
+                    format!(r#"{{"a":{expected}}}"#)
+                );
+            }
+            None => assert!(value.is_err()),
+        }
+    }
+
+    #[test]
+    fn test_xml_schema_declarations_by_parent() {
+        let xsd = r#"<xs:schema xmlns:xs="http://www.w3.org/2001/XMLSchema">
+  <xs:complexType name="Order">
+    <xs:sequence><xs:element name="item" type="xs:string"/></xs:sequence>
+    <xs:attribute name="id" type="xs:string"/>
+  </xs:complexType>
+  <xs:group name="Items">
+    <xs:sequence><xs:element name="item" type="xs:int"/></xs:sequence>
+  </xs:group>
+  <xs:element name="shop">
+    <xs:complexType>
+      <xs:sequence>
+        <xs:element name="list">
+          <xs:complexType>
+            <xs:group ref="Items" maxOccurs="unbounded"/>
+            <xs:attribute name="id" type="xs:int"/>
+          </xs:complexType>
+        </xs:element>
+        <xs:element name="order" type="Order"/>
+      </xs:sequence>
+    </xs:complexType>
+  </xs:element>
+</xs:schema>"#;
+        let options = LoadOptions {
+            xml: XmlLoadOptions {
+                schema: Some(XmlSchema::parse(xsd.as_bytes()).unwrap()),
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        let input = r#"<shop><list id="1"><item>7</item></list><order id="007"><item>007</item></order></shop>"#;
+        let value = load_input_with_options(input.as_bytes(), Format::Xml, &options).unwrap();
+        let output = dump_value(&value, Format::Json, true).unwrap();
+        assert_eq!(
+            String::from_utf8(output).unwrap(),
+            r#"{"shop":{"list":{"@id":1,"item":[7]},"order":{"@id":"007","item":"007"}}}"#
+        );
     }
 
     #[cfg(feature = "hocon")]
@@ -1,29 +1,384 @@
+use std::collections::HashMap;
+use std::fs;
 use std::io::{self, Read, Write};
+use std::path::PathBuf;
 
-use anyhow::Result;
-use clap::Parser;
+use anyhow::{Context, Result, bail};
+use clap::builder::{PossibleValuesParser, TypedValueParser};
+use clap::{Parser, ValueEnum};
 
-use convfmt::{Format, dump_value, load_input};
+#[cfg(feature = "sqlite")]
+use convfmt::SqliteLoadOptions;
+use convfmt::{
+    ArrayStyle, BsonLoadOptions, ColumnOrder, CompactOutput, CsvDumpOptions, CsvLoadOptions,
+    DumpOptions, ExtJsonMode, Format, Formatting, HtmlDumpOptions, KeyOrder, LineEnding,
+    LoadOptions, NanPolicy, NullPolicy, PlistDumpOptions, SqlDialect, SqlDumpOptions,
+    TomlDumpOptions, TypeMismatch, XmlConvention, XmlDumpOptions, XmlLoadOptions, XmlMapping,
+    XmlNamespaces, XmlSchema, dump_value_with_options, load_input_with_options, parse_column_types,
+};
 
 #[derive(Parser, Debug)]
 #[command(about, version, author)]
 struct CliArgs {
-    #[arg(short, long, value_enum)]
+    #[arg(short, long, value_parser = format_parser(Format::is_input))]
     from: Format,
 
-    #[arg(short, long, value_enum)]
+    #[arg(short, long, value_parser = format_parser(Format::is_output))]
     to: Format,
 
     #[arg(short, long)]
-    /// Compress output if possible (default = false)
+    /// Compress output if possible, warns when output format has no compact form (default = false)
     compact: bool,
+
+    #[arg(long, value_name = "N", conflicts_with = "indent_tabs")]
+    /// Spaces per indentation level of pretty output, default of format otherwise
+    indent: Option<usize>,
+
+    #[arg(long)]
+    /// Indent pretty output with tabs
+    indent_tabs: bool,
+
+    #[arg(long, value_enum, default_value_t = LineEnding::Lf)]
+    /// Line ending of text output
+    line_ending: LineEnding,
+
+    #[arg(long)]
+    /// End text output with a line ending
+    final_newline: bool,
+
+    #[arg(long, value_name = "WIDTH")]
+    /// Keep arrays and objects which fit into this width on one line in pretty json and toml
+    max_width: Option<usize>,
+
+    #[arg(long)]
+    /// Sort keys of all objects, by default keys keep the order of input
+    sort_keys: bool,
+
+    #[arg(long, value_name = "KEYS", value_delimiter = ',')]
+    /// Comma separated keys which go first in every object, e.g. `apiVersion,kind,metadata`
+    key_order: Vec<String>,
+
+    #[arg(long, value_enum, default_value_t = NanPolicy::Error)]
+    /// What to do with NaN and infinity when output format doesn't support them (json)
+    nan_policy: NanPolicy,
+
+    #[arg(long, value_enum, default_value_t = NullPolicy::Error)]
+    /// What to do with nulls when output format doesn't support them (toml, plist)
+    null_policy: NullPolicy,
+
+    #[arg(long, value_name = "VALUE", required_if_eq("null_policy", "sentinel"))]
+    /// Replacement of nulls for `--null-policy sentinel`
+    null_sentinel: Option<String>,
+
+    #[arg(long, value_enum, default_value_t = ExtJsonMode::Relaxed)]
+    /// Extended json mode of bson values like ObjectId and datetime
+    bson_extjson: ExtJsonMode,
+
+    #[arg(long)]
+    /// Load bson input as a stream of concatenated documents (e.g. `mongodump`) into an array
+    bson_stream: bool,
+
+    #[arg(long, value_enum, default_value_t = ColumnOrder::FirstSeen)]
+    /// Order of csv columns in output
+    csv_column_order: ColumnOrder,
+
+    #[arg(long)]
+    /// Dump nested csv values as json instead of flattening them into columns
+    csv_no_flatten: bool,
+
+    #[arg(long, value_enum, default_value_t = ArrayStyle::Indexed)]
+    /// How flattened arrays are written to csv
+    csv_arrays: ArrayStyle,
+
+    #[arg(long, default_value = ";")]
+    /// Separator of joined csv array items
+    csv_array_separator: String,
+
+    #[arg(long)]
+    /// Rebuild nested values from flattened csv column names
+    csv_unflatten: bool,
+
+    #[arg(long, default_value = ".", value_parser = parse_separator)]
+    /// Separator of nested keys in flattened csv column names
+    csv_key_separator: String,
+
+    #[arg(long)]
+    /// Keep csv cells as strings instead of guessing bool and number types
+    csv_no_infer: bool,
+
+    #[arg(long, value_name = "SPEC")]
+    /// Types of csv columns, e.g. `id:string,price:float,active:bool,created:datetime`
+    csv_types: Option<String>,
+
+    #[arg(long, value_name = "FILE")]
+    /// File with types of csv columns, one `column:type` per line
+    csv_schema: Option<PathBuf>,
+
+    #[arg(long, value_enum, default_value_t = TypeMismatch::Error)]
+    /// What to do with csv cells that don't match their column type
+    csv_type_mismatch: TypeMismatch,
+
+    #[arg(long)]
+    /// Spreadsheet sheet name, or zero-based index for input
+    sheet: Option<String>,
+
+    #[arg(long)]
+    /// Dump html fragment without `<html>`, `<head>` and `<body>`
+    html_fragment: bool,
+
+    #[arg(long)]
+    /// Title of html page
+    html_title: Option<String>,
+
+    #[arg(long)]
+    /// Write binary plist instead of xml
+    plist_binary: bool,
+
+    #[arg(long, value_name = "KEY")]
+    /// Key whose base64 string values are written as plist data, may be repeated
+    plist_data: Vec<String>,
+
+    #[arg(long)]
+    /// Write strings with an ISO-8601 date as plist dates, datetimes of toml and bson always are
+    plist_dates: bool,
+
+    #[arg(long, value_enum, default_value_t = SqlDialect::Sqlite)]
+    /// Dialect of sql output
+    sql_dialect: SqlDialect,
+
+    #[arg(long, value_name = "NAME", default_value = "data")]
+    /// Table name of sql and sqlite output
+    sql_table: String,
+
+    #[arg(long)]
+    /// Dump only sql inserts without `CREATE TABLE`
+    sql_no_create: bool,
+
+    #[cfg(feature = "sqlite")]
+    #[arg(long, value_name = "SELECT")]
+    /// Query to read from sqlite input instead of the whole table
+    sqlite_query: Option<String>,
+
+    #[cfg(feature = "sqlite")]
+    #[arg(long, value_name = "NAME")]
+    /// Table of sqlite input, may be omitted when database has a single table
+    sqlite_table: Option<String>,
+
+    #[arg(long)]
+    /// Write strings with a date as toml datetimes, dates of plist and bson always are
+    toml_datetimes: bool,
+
+    #[arg(long, value_name = "KEY")]
+    /// Write top-level array of toml output under this key
+    toml_root: Option<String>,
+
+    #[arg(long, value_name = "DEPTH")]
+    /// Write toml tables nested deeper than this as inline tables
+    toml_inline_depth: Option<usize>,
+
+    #[arg(long, value_name = "SIZE")]
+    /// Write toml tables and arrays of tables which fit into this number of characters inline
+    toml_inline_size: Option<usize>,
+
+    #[arg(long, value_enum, default_value_t = XmlNamespaces::Keep)]
+    /// How prefixed names of xml input are read
+    xml_namespaces: XmlNamespaces,
+
+    #[arg(long, value_name = "PREFIX=URI", value_parser = parse_namespace)]
+    /// Namespace declared on root element of xml output, may be repeated. `{uri}local` names are
+    /// written with its prefix
+    xml_ns: Vec<(String, String)>,
+
+    #[arg(long, value_enum, default_value_t = XmlConvention::Default)]
+    /// Convention of mapping xml elements, attributes and text to values
+    xml_convention: XmlConvention,
+
+    #[arg(long)]
+    /// Prefix of xml attribute keys (default = @, empty for gdata)
+    xml_attr_prefix: Option<String>,
+
+    #[arg(long)]
+    /// Key of xml element text (default = #text, $ for badgerfish, $t for gdata)
+    xml_text_key: Option<String>,
+
+    #[arg(long, value_name = "NAME")]
+    /// Xml element loaded as array even when it appears once, may be repeated
+    xml_array: Vec<String>,
+
+    #[arg(long)]
+    /// Keep xml attributes and text as strings instead of guessing bool and number types
+    xml_no_infer: bool,
+
+    #[arg(long, value_name = "FILE")]
+    /// XML Schema (XSD) with types of xml elements and attributes and elements which are arrays
+    xml_schema: Option<PathBuf>,
+
+    #[arg(long, value_name = "NAME")]
+    /// Name of xml output root element [default: single top-level key or root]
+    xml_root: Option<String>,
+
+    #[arg(long, value_name = "NAME")]
+    /// Wrap top-level array of xml output into root element with items of this name
+    xml_item: Option<String>,
+
+    #[arg(long)]
+    /// Write xml declaration
+    xml_declaration: bool,
+
+    #[arg(long)]
+    /// Write empty xml elements as `<name></name>` instead of `<name/>`
+    xml_no_self_closing: bool,
+}
+
+/// Parser of formats passing `filter`, other formats aren't possible values.
+fn format_parser(filter: fn(Format) -> bool) -> impl TypedValueParser<Value = Format> {
+    let names = Format::value_variants()
+        .iter()
+        .filter(|format| filter(**format))
+        .filter_map(ValueEnum::to_possible_value);
+    PossibleValuesParser::new(names)
+        .map(|name| Format::from_str(&name, false).expect("possible values are formats"))
+}
+
+fn parse_namespace(s: &str) -> Result<(String, String)> {
+    let (prefix, uri) = s
+        .split_once('=')
+        .context("Expected namespace as PREFIX=URI")?;
+    Ok((prefix.to_string(), uri.to_string()))
+}
+
+fn parse_separator(s: &str) -> Result<String> {
+    if s.is_empty() {
+        bail!("Separator can't be empty");
+    }
+    Ok(s.to_string())
+}
+
+impl CliArgs {
+    fn load_options(&self) -> Result<LoadOptions> {
+        let mut column_types = HashMap::new();
+        if let Some(path) = &self.csv_schema {
+            let schema = fs::read_to_string(path)
+                .with_context(|| format!("Can't read csv schema {}", path.display()))?;
+            column_types.extend(parse_column_types(&schema)?);
+        }
+        if let Some(spec) = &self.csv_types {
+            column_types.extend(parse_column_types(spec)?);
+        }
+
+        let xml_schema = match &self.xml_schema {
+            Some(path) => {
+                let xsd = fs::read(path)
+                    .with_context(|| format!("Can't read xml schema {}", path.display()))?;
+                Some(XmlSchema::parse(&xsd)?)
+            }
+            None => None,
+        };
+
+        Ok(LoadOptions {
+            bson: BsonLoadOptions {
+                extjson: self.bson_extjson,
+                stream: self.bson_stream,
+            },
+            csv: CsvLoadOptions {
+                unflatten: self.csv_unflatten,
+                key_separator: self.csv_key_separator.clone(),
+                infer_types: !self.csv_no_infer,
+                column_types,
+                type_mismatch: self.csv_type_mismatch,
+            },
+            sheet: self.sheet.clone(),
+            #[cfg(feature = "sqlite")]
+            sqlite: SqliteLoadOptions {
+                table: self.sqlite_table.clone(),
+                query: self.sqlite_query.clone(),
+            },
+            xml: XmlLoadOptions {
+                namespaces: self.xml_namespaces,
+                mapping: self.xml_mapping(),
+                infer_types: !self.xml_no_infer,
+                schema: xml_schema,
+            },
+        })
+    }
+
+    fn xml_mapping(&self) -> XmlMapping {
+        XmlMapping {
+            convention: self.xml_convention,
+            attr_prefix: self.xml_attr_prefix.clone(),
+            text_key: self.xml_text_key.clone(),
+            force_arrays: self.xml_array.clone(),
+        }
+    }
+
+    fn dump_options(&self) -> DumpOptions {
+        DumpOptions {
+            is_compact: self.compact,
+            key_order: KeyOrder {
+                sort: self.sort_keys,
+                first: self.key_order.clone(),
+            },
+            formatting: Formatting {
+                indent: self.indent,
+                indent_tabs: self.indent_tabs,
+                line_ending: self.line_ending,
+                final_newline: self.final_newline,
+                max_width: self.max_width,
+            },
+            nan_policy: self.nan_policy,
+            null_policy: self.null_policy,
+            null_sentinel: self.null_sentinel.clone().unwrap_or_default(),
+            csv: CsvDumpOptions {
+                column_order: self.csv_column_order,
+                flatten: !self.csv_no_flatten,
+                array_style: self.csv_arrays,
+                key_separator: self.csv_key_separator.clone(),
+                array_separator: self.csv_array_separator.clone(),
+            },
+            html: HtmlDumpOptions {
+                fragment: self.html_fragment,
+                title: self.html_title.clone(),
+            },
+            plist: PlistDumpOptions {
+                binary: self.plist_binary,
+                data_keys: self.plist_data.clone(),
+                dates: self.plist_dates,
+            },
+            sql: SqlDumpOptions {
+                dialect: self.sql_dialect,
+                table: self.sql_table.clone(),
+                create_table: !self.sql_no_create,
+            },
+            toml: TomlDumpOptions {
+                datetimes: self.toml_datetimes,
+                root_key: self.toml_root.clone(),
+                inline_depth: self.toml_inline_depth,
+                inline_size: self.toml_inline_size,
+            },
+            sheet: self.sheet.clone(),
+            xml: XmlDumpOptions {
+                mapping: self.xml_mapping(),
+                namespaces: self.xml_ns.clone(),
+                root: self.xml_root.clone(),
+                item_name: self.xml_item.clone(),
+                declaration: self.xml_declaration,
+                self_closing: !self.xml_no_self_closing,
+            },
+        }
+    }
 }
 
 fn run_app() -> Result<()> {
     let args = CliArgs::parse();
+    if args.compact && args.to.compact_output() == CompactOutput::Unsupported {
+        eprintln!(
+            "Warning: {} output has no compact form, --compact is ignored",
+            args.to
+        );
+    }
     let input = read_input()?;
-    let value = load_input(&input, args.from)?;
-    let output = dump_value(&value, args.to, args.compact)?;
+    let value = load_input_with_options(&input, args.from, &args.load_options()?)?;
+    let output = dump_value_with_options(&value, args.to, &args.dump_options())?;
     write_output(&output)?;
     Ok(())
 }
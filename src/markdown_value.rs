@@ -0,0 +1,157 @@
+use anyhow::Result;
+use serde_json::Value as JsonValue;
+use unicode_width::UnicodeWidthStr;
+
+use crate::csv_value::{CsvDumpOptions, format_cell, value_to_table};
+
+/// Dumps array of objects as GitHub-flavored markdown table, any other value as nested bullet list.
+pub fn json_to_markdown(json: &[u8], options: &CsvDumpOptions) -> Result<Vec<u8>> {
+    let value: JsonValue = serde_json::from_slice(json)?;
+    let mut buffer = String::new();
+
+    match value {
+        JsonValue::Array(items) if !items.is_empty() && items.iter().all(JsonValue::is_object) => {
+            dump_table(JsonValue::Array(items), options, &mut buffer)?;
+        }
+        value => dump_list(&value, 0, &mut buffer),
+    }
+    Ok(buffer.into_bytes())
+}
+
+/// Columns are padded to the display width of cells, so wide characters like CJK and emoji
+/// line up in monospace fonts.
+fn dump_table(value: JsonValue, options: &CsvDumpOptions, buffer: &mut String) -> Result<()> {
+    let table = value_to_table(value, options)?;
+
+    let mut rows = Vec::with_capacity(table.rows.len());
+    for row in &table.rows {
+        let mut cells = Vec::with_capacity(table.header.len());
+        for key in &table.header {
+            cells.push(match row.get(key) {
+                Some(value) => escape_cell(&format_cell(value)?),
+                None => String::new(),
+            });
+        }
+        rows.push(cells);
+    }
+
+    let header: Vec<String> = table.header.iter().map(|key| escape_cell(key)).collect();
+    let widths: Vec<usize> = header
+        .iter()
+        .enumerate()
+        .map(|(column, key)| {
+            rows.iter()
+                .map(|cells| cells[column].width())
+                .chain([key.width(), 3])
+                .max()
+                .unwrap_or_default()
+        })
+        .collect();
+    // numeric columns are right-aligned
+    let numeric: Vec<bool> = table
+        .header
+        .iter()
+        .map(|key| {
+            let mut values = table.rows.iter().filter_map(|row| row.get(key));
+            values.clone().any(JsonValue::is_number)
+                && values.all(|value| value.is_number() || value.is_null())
+        })
+        .collect();
+
+    push_row(buffer, &header, &widths, &numeric);
+    buffer.push('|');
+    for (width, numeric) in widths.iter().zip(&numeric) {
+        buffer.push(' ');
+        if *numeric {
+            buffer.push_str(&"-".repeat(width - 1));
+            buffer.push(':');
+        } else {
+            buffer.push_str(&"-".repeat(*width));
+        }
+        buffer.push_str(" |");
+    }
+    buffer.push('\n');
+    for cells in &rows {
+        push_row(buffer, cells, &widths, &numeric);
+    }
+    Ok(())
+}
+
+fn push_row(buffer: &mut String, cells: &[String], widths: &[usize], numeric: &[bool]) {
+    buffer.push('|');
+    for ((cell, width), numeric) in cells.iter().zip(widths).zip(numeric) {
+        let padding = " ".repeat(width - cell.width());
+        buffer.push(' ');
+        if *numeric {
+            buffer.push_str(&padding);
+            buffer.push_str(cell);
+        } else {
+            buffer.push_str(cell);
+            buffer.push_str(&padding);
+        }
+        buffer.push_str(" |");
+    }
+    buffer.push('\n');
+}
+
+fn escape_cell(s: &str) -> String {
+    escape_inline(s)
+        .replace("\r\n", "<br>")
+        .replace('\n', "<br>")
+}
+
+/// Escapes characters which start inline markup or end table cells.
+fn escape_inline(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        if matches!(c, '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '|') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+fn dump_list(value: &JsonValue, depth: usize, buffer: &mut String) {
+    let indent = "  ".repeat(depth);
+    match value {
+        JsonValue::Object(items) => {
+            for (key, item) in items {
+                buffer.push_str(&format!("{indent}- **{}**:", escape_inline(key)));
+                push_item(item, depth, buffer);
+            }
+        }
+        JsonValue::Array(items) => {
+            for item in items {
+                buffer.push_str(&format!("{indent}-"));
+                push_item(item, depth, buffer);
+            }
+        }
+        scalar => buffer.push_str(&format!("{indent}{}\n", format_scalar(scalar))),
+    }
+}
+
+fn push_item(item: &JsonValue, depth: usize, buffer: &mut String) {
+    match item {
+        JsonValue::Object(map) if !map.is_empty() => {
+            buffer.push('\n');
+            dump_list(item, depth + 1, buffer);
+        }
+        JsonValue::Array(items) if !items.is_empty() => {
+            buffer.push('\n');
+            dump_list(item, depth + 1, buffer);
+        }
+        item => {
+            buffer.push(' ');
+            buffer.push_str(&format_scalar(item));
+            buffer.push('\n');
+        }
+    }
+}
+
+fn format_scalar(value: &JsonValue) -> String {
+    match value {
+        JsonValue::String(s) => escape_inline(&s.replace('\n', " ")),
+        value => value.to_string(),
+    }
+}
@@ -0,0 +1,62 @@
+use anyhow::{Result, bail};
+use serde_yaml::Value as YamlValue;
+
+use crate::Format;
+use crate::null_policy::{key_path, yaml_key_name};
+
+/// What to do with NaN and infinite floats when output format doesn't support them.
+#[derive(Debug, Copy, Clone, Default, PartialEq, clap::ValueEnum)]
+pub enum NanPolicy {
+    /// Fail on the first NaN or infinity
+    #[default]
+    Error,
+    /// Replace them with nulls
+    Null,
+    /// Replace them with strings `NaN`, `Infinity` and `-Infinity`
+    String,
+}
+
+/// Applies NaN policy to value dumped as `format`, errors point to the path of float.
+/// Json based values can't contain such floats, so only values of other formats are converted.
+/// Returns whether any float was replaced.
+pub(crate) fn replace_nan(yaml: &mut YamlValue, policy: NanPolicy, format: Format) -> Result<bool> {
+    replace(yaml, policy, format, "")
+}
+
+fn replace(yaml: &mut YamlValue, policy: NanPolicy, format: Format, path: &str) -> Result<bool> {
+    let mut is_replaced = false;
+    match yaml {
+        YamlValue::Number(n) if n.is_nan() || n.is_infinite() => {
+            let name = match n.as_f64() {
+                Some(f) if f.is_nan() => "NaN",
+                Some(f) if f > 0.0 => "Infinity",
+                _ => "-Infinity",
+            };
+            *yaml = match policy {
+                NanPolicy::Error if path.is_empty() => {
+                    bail!("{name} can't be written in {format}")
+                }
+                NanPolicy::Error => bail!("{name} at {path} can't be written in {format}"),
+                NanPolicy::Null => YamlValue::Null,
+                NanPolicy::String => YamlValue::String(name.to_string()),
+            };
+            is_replaced = true;
+        }
+        YamlValue::Sequence(items) => {
+            for (i, item) in items.iter_mut().enumerate() {
+                is_replaced |= replace(item, policy, format, &format!("{path}[{i}]"))?;
+            }
+        }
+        YamlValue::Mapping(map) => {
+            for (key, value) in map.iter_mut() {
+                let path = key_path(path, &yaml_key_name(key));
+                is_replaced |= replace(value, policy, format, &path)?;
+            }
+        }
+        YamlValue::Tagged(tagged) => {
+            is_replaced |= replace(&mut tagged.value, policy, format, path)?;
+        }
+        _ => (),
+    }
+    Ok(is_replaced)
+}
@@ -0,0 +1,229 @@
+use anyhow::{Result, bail};
+use serde_json::Value as JsonValue;
+use serde_yaml::Value as YamlValue;
+use serde_yaml::value::{Tag, TaggedValue};
+
+use crate::Format;
+
+/// What to do with nulls when output format doesn't support them.
+#[derive(Debug, Copy, Clone, Default, PartialEq, clap::ValueEnum)]
+pub enum NullPolicy {
+    /// Fail on the first null
+    #[default]
+    Error,
+    /// Drop object keys with null values, nulls in arrays are errors
+    DropKeys,
+    /// Drop object keys and array items with null values
+    Drop,
+    /// Replace nulls with empty strings
+    Empty,
+    /// Replace nulls with the sentinel string
+    Sentinel,
+}
+
+/// Applies null policy to value dumped as `format`, errors point to the path of null.
+pub(crate) fn replace_nulls(
+    json: JsonValue,
+    policy: NullPolicy,
+    sentinel: &str,
+    format: Format,
+) -> Result<JsonValue> {
+    NullReplacer {
+        policy,
+        sentinel,
+        format,
+    }
+    .replace_root(json)
+}
+
+/// Same as `replace_nulls` for values of formats which aren't based on json.
+pub(crate) fn replace_yaml_nulls(
+    yaml: YamlValue,
+    policy: NullPolicy,
+    sentinel: &str,
+    format: Format,
+) -> Result<YamlValue> {
+    NullReplacer {
+        policy,
+        sentinel,
+        format,
+    }
+    .replace_root(yaml)
+}
+
+pub(crate) fn has_yaml_nulls(yaml: &YamlValue) -> bool {
+    match yaml {
+        YamlValue::Null => true,
+        YamlValue::Sequence(items) => items.iter().any(has_yaml_nulls),
+        YamlValue::Mapping(map) => map.values().any(has_yaml_nulls),
+        YamlValue::Tagged(tagged) => has_yaml_nulls(&tagged.value),
+        _ => false,
+    }
+}
+
+/// Path of object key for error messages.
+pub(crate) fn key_path(path: &str, key: &str) -> String {
+    match path {
+        "" => key.to_string(),
+        path => format!("{path}.{key}"),
+    }
+}
+
+/// Name of yaml mapping key for error messages.
+pub(crate) fn yaml_key_name(key: &YamlValue) -> String {
+    match key {
+        YamlValue::String(s) => s.clone(),
+        YamlValue::Number(n) => n.to_string(),
+        YamlValue::Bool(b) => b.to_string(),
+        key => format!("{key:?}"),
+    }
+}
+
+/// Json and yaml values share the walk which replaces nulls.
+trait Tree: Sized {
+    type Key;
+
+    fn into_node(self) -> Node<Self>;
+    fn from_node(node: Node<Self>) -> Self;
+    fn from_string(s: String) -> Self;
+    fn key_name(key: &Self::Key) -> String;
+}
+
+enum Node<T: Tree> {
+    Null,
+    Array(Vec<T>),
+    Object(Vec<(T::Key, T)>),
+    Tagged(Tag, T),
+    Scalar(T),
+}
+
+impl Tree for JsonValue {
+    type Key = String;
+
+    fn into_node(self) -> Node<Self> {
+        match self {
+            JsonValue::Null => Node::Null,
+            JsonValue::Array(items) => Node::Array(items),
+            JsonValue::Object(map) => Node::Object(map.into_iter().collect()),
+            json => Node::Scalar(json),
+        }
+    }
+
+    fn from_node(node: Node<Self>) -> Self {
+        match node {
+            Node::Null => JsonValue::Null,
+            Node::Array(items) => JsonValue::Array(items),
+            Node::Object(entries) => JsonValue::Object(entries.into_iter().collect()),
+            Node::Tagged(_, json) | Node::Scalar(json) => json,
+        }
+    }
+
+    fn from_string(s: String) -> Self {
+        JsonValue::String(s)
+    }
+
+    fn key_name(key: &String) -> String {
+        key.clone()
+    }
+}
+
+impl Tree for YamlValue {
+    type Key = YamlValue;
+
+    fn into_node(self) -> Node<Self> {
+        match self {
+            YamlValue::Null => Node::Null,
+            YamlValue::Sequence(items) => Node::Array(items),
+            YamlValue::Mapping(map) => Node::Object(map.into_iter().collect()),
+            YamlValue::Tagged(tagged) => Node::Tagged(tagged.tag, tagged.value),
+            yaml => Node::Scalar(yaml),
+        }
+    }
+
+    fn from_node(node: Node<Self>) -> Self {
+        match node {
+            Node::Null => YamlValue::Null,
+            Node::Array(items) => YamlValue::Sequence(items),
+            Node::Object(entries) => YamlValue::Mapping(entries.into_iter().collect()),
+            Node::Tagged(tag, value) => YamlValue::Tagged(Box::new(TaggedValue { tag, value })),
+            Node::Scalar(yaml) => yaml,
+        }
+    }
+
+    fn from_string(s: String) -> Self {
+        YamlValue::String(s)
+    }
+
+    fn key_name(key: &YamlValue) -> String {
+        yaml_key_name(key)
+    }
+}
+
+struct NullReplacer<'a> {
+    policy: NullPolicy,
+    sentinel: &'a str,
+    format: Format,
+}
+
+impl NullReplacer<'_> {
+    fn replace_root<T: Tree>(&self, value: T) -> Result<T> {
+        match self.replace(value, "")? {
+            Some(value) => Ok(value),
+            None => bail!("Root value of {} can't be null", self.format),
+        }
+    }
+
+    /// `None` when value is dropped.
+    fn replace<T: Tree>(&self, value: T, path: &str) -> Result<Option<T>> {
+        let node = match value.into_node() {
+            Node::Null => return Ok(self.replace_null(path)?.map(T::from_string)),
+            Node::Array(items) => {
+                let mut replaced = Vec::with_capacity(items.len());
+                for (i, item) in items.into_iter().enumerate() {
+                    let path = format!("{path}[{i}]");
+                    match self.replace(item, &path)? {
+                        Some(item) => replaced.push(item),
+                        None => self.drop_item(&path)?,
+                    }
+                }
+                Node::Array(replaced)
+            }
+            Node::Object(entries) => {
+                let mut replaced = Vec::with_capacity(entries.len());
+                for (key, value) in entries {
+                    let path = key_path(path, &T::key_name(&key));
+                    if let Some(value) = self.replace(value, &path)? {
+                        replaced.push((key, value));
+                    }
+                }
+                Node::Object(replaced)
+            }
+            Node::Tagged(tag, value) => match self.replace(value, path)? {
+                Some(value) => Node::Tagged(tag, value),
+                None => return Ok(None),
+            },
+            node => node,
+        };
+        Ok(Some(T::from_node(node)))
+    }
+
+    /// Replacement string of null, `None` when null is dropped.
+    fn replace_null(&self, path: &str) -> Result<Option<String>> {
+        match self.policy {
+            NullPolicy::Error if path.is_empty() => {
+                bail!("Root value of {} can't be null", self.format)
+            }
+            NullPolicy::Error => bail!("Null at {path} can't be written in {}", self.format),
+            NullPolicy::DropKeys | NullPolicy::Drop => Ok(None),
+            NullPolicy::Empty => Ok(Some(String::new())),
+            NullPolicy::Sentinel => Ok(Some(self.sentinel.to_string())),
+        }
+    }
+
+    fn drop_item(&self, path: &str) -> Result<()> {
+        if self.policy != NullPolicy::Drop {
+            bail!("Null at {path} can't be written in {}", self.format);
+        }
+        Ok(())
+    }
+}
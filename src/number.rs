@@ -0,0 +1,121 @@
+use anyhow::{Result, bail};
+use serde::ser::Error;
+use serde::{Serialize, Serializer};
+use serde_json::{Number, Value as JsonValue};
+
+/// Json value for serializers of other formats, which don't understand arbitrary precision
+/// numbers of serde_json. Numbers are serialized as integers up to 128 bits or as floats when
+/// float has exactly the same value.
+#[derive(Clone, Copy)]
+pub(crate) struct NativeNumbers<'a> {
+    json: &'a JsonValue,
+    /// Integers which don't fit into `i64` are errors, like in toml
+    is_i64_only: bool,
+}
+
+impl<'a> NativeNumbers<'a> {
+    pub(crate) fn new(json: &'a JsonValue, is_i64_only: bool) -> Self {
+        Self { json, is_i64_only }
+    }
+
+    fn with(self, json: &'a JsonValue) -> Self {
+        Self { json, ..self }
+    }
+}
+
+impl Serialize for NativeNumbers<'_> {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self.json {
+            JsonValue::Number(n) => {
+                if let Some(i) = n.as_i64() {
+                    serializer.serialize_i64(i)
+                } else if self.is_i64_only && is_integer(n) {
+                    Err(S::Error::custom(format!(
+                        "Integer {n} is out of range of i64"
+                    )))
+                } else if let Some(u) = n.as_u64() {
+                    serializer.serialize_u64(u)
+                } else if let Some(i) = n.as_i128() {
+                    serializer.serialize_i128(i)
+                } else if let Some(u) = n.as_u128() {
+                    serializer.serialize_u128(u)
+                } else {
+                    serializer.serialize_f64(to_exact_f64(n).map_err(S::Error::custom)?)
+                }
+            }
+            JsonValue::Array(items) => {
+                serializer.collect_seq(items.iter().map(|item| self.with(item)))
+            }
+            JsonValue::Object(map) => {
+                serializer.collect_map(map.iter().map(|(key, value)| (key, self.with(value))))
+            }
+            value => value.serialize(serializer),
+        }
+    }
+}
+
+/// Parses number as integer or float, numbers which would be rounded by float keep all of their
+/// digits. `None` for non-finite numbers.
+pub(crate) fn parse_number(s: &str) -> Option<Number> {
+    if let Ok(i) = s.parse::<i64>() {
+        return Some(i.into());
+    }
+    if let Ok(u) = s.parse::<u64>() {
+        return Some(u.into());
+    }
+    if let Ok(n) = s.parse::<Number>()
+        && to_exact_f64(&n).is_err()
+    {
+        return Some(n);
+    }
+    s.parse::<f64>().ok().and_then(Number::from_f64)
+}
+
+/// Number which other formats hold as integer up to 128 bits or as float with the same value.
+pub(crate) fn is_native(n: &Number) -> bool {
+    n.as_i128().is_some() || n.as_u128().is_some() || to_exact_f64(n).is_ok()
+}
+
+/// Number written without fraction and exponent.
+pub(crate) fn is_integer(n: &Number) -> bool {
+    !n.as_str().contains(['.', 'e', 'E'])
+}
+
+/// Float with exactly the same value as number, error when float would round it.
+pub(crate) fn to_exact_f64(n: &Number) -> Result<f64> {
+    if let Some(f) = n.as_f64()
+        && f.is_finite()
+        && decimal_parts(n.as_str()) == decimal_parts(&format!("{f:e}"))
+    {
+        return Ok(f);
+    }
+    bail!("Number {n} can't be represented without loss of precision")
+}
+
+/// Sign, significant digits and exponent of decimal number, zero has no digits.
+fn decimal_parts(s: &str) -> Option<(bool, String, i64)> {
+    let (is_negative, s) = match s.strip_prefix('-') {
+        Some(s) => (true, s),
+        None => (false, s.strip_prefix('+').unwrap_or(s)),
+    };
+    let (mantissa, exponent) = match s.split_once(['e', 'E']) {
+        Some((mantissa, exponent)) => (mantissa, exponent.parse::<i64>().ok()?),
+        None => (s, 0),
+    };
+    let (integer, fraction) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+    let digits = format!("{integer}{fraction}");
+    if !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+
+    let significant = digits.trim_end_matches('0');
+    let exponent = exponent - fraction.len() as i64 + (digits.len() - significant.len()) as i64;
+    let significant = significant.trim_start_matches('0');
+    if significant.is_empty() {
+        return Some((false, String::new(), 0));
+    }
+    Some((is_negative, significant.to_string(), exponent))
+}
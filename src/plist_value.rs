@@ -0,0 +1,250 @@
+use std::io::Cursor;
+
+use anyhow::{Result, bail};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use plist::{Date, Dictionary, Value as PlistValue, XmlWriteOptions};
+use serde::{Serialize, Serializer};
+use serde_json::{Map, Number, Value as JsonValue};
+
+use crate::bson_value::{extjson, number_double};
+use crate::formatting::Formatting;
+use crate::key_order::KeyOrder;
+use crate::number::to_exact_f64;
+
+#[derive(Debug, Clone, Default)]
+pub struct PlistDumpOptions {
+    /// Write binary `bplist00` instead of xml
+    pub binary: bool,
+    /// Keys whose base64 string values are written as data
+    pub data_keys: Vec<String>,
+    /// Strings with an ISO-8601 datetime are written as dates, extended json dates of bson and
+    /// datetimes of toml always are
+    pub dates: bool,
+}
+
+/// Data is serialized as base64 string, date as ISO-8601 string and uid as integer.
+#[derive(Debug)]
+pub struct PlistWrapper(PlistValue);
+
+impl PlistWrapper {
+    pub(crate) fn with_key_order(&self, order: &KeyOrder) -> Self {
+        PlistWrapper(order_plist(self.0.clone(), order))
+    }
+
+    /// Dates become extended json dates like bson datetimes, NaN and infinity `$numberDouble`.
+    pub(crate) fn to_extjson(&self) -> JsonValue {
+        plist_to_extjson(&self.0)
+    }
+}
+
+impl Serialize for PlistWrapper {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        Typed(&self.0).serialize(serializer)
+    }
+}
+
+struct Typed<'a>(&'a PlistValue);
+
+impl Serialize for Typed<'_> {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self.0 {
+            PlistValue::Array(items) => serializer.collect_seq(items.iter().map(Typed)),
+            PlistValue::Dictionary(dictionary) => {
+                serializer.collect_map(dictionary.iter().map(|(key, value)| (key, Typed(value))))
+            }
+            PlistValue::Data(data) => serializer.serialize_str(&BASE64.encode(data)),
+            PlistValue::Date(date) => serializer.serialize_str(&date.to_xml_format()),
+            PlistValue::Uid(uid) => serializer.serialize_u64(uid.get()),
+            value => value.serialize(serializer),
+        }
+    }
+}
+
+/// Binary, xml and OpenStep ASCII property lists are detected by content.
+pub fn load_plist(input: &[u8]) -> Result<PlistWrapper> {
+    Ok(PlistWrapper(PlistValue::from_reader(Cursor::new(input))?))
+}
+
+pub fn dump_plist(
+    plist: &PlistWrapper,
+    is_compact: bool,
+    options: &PlistDumpOptions,
+    formatting: &Formatting,
+) -> Result<Vec<u8>> {
+    write_plist(&plist.0, is_compact, options, formatting)
+}
+
+/// Extended json dates are converted into dates, ISO-8601 datetime strings only with `dates`,
+/// strings of `data_keys` into data.
+pub fn json_to_plist(
+    json: &[u8],
+    is_compact: bool,
+    options: &PlistDumpOptions,
+    formatting: &Formatting,
+) -> Result<Vec<u8>> {
+    let json: JsonValue = serde_json::from_slice(json)?;
+    extjson_to_plist(json, is_compact, options, formatting)
+}
+
+/// Like `json_to_plist`, extended json `{"$numberDouble": "NaN"}` keeps NaN and infinity.
+pub(crate) fn extjson_to_plist(
+    json: JsonValue,
+    is_compact: bool,
+    options: &PlistDumpOptions,
+    formatting: &Formatting,
+) -> Result<Vec<u8>> {
+    let plist = json_value_to_plist(json, None, options)?;
+    write_plist(&plist, is_compact, options, formatting)
+}
+
+fn write_plist(
+    plist: &PlistValue,
+    is_compact: bool,
+    options: &PlistDumpOptions,
+    formatting: &Formatting,
+) -> Result<Vec<u8>> {
+    let mut buffer = Vec::new();
+    if options.binary {
+        plist.to_writer_binary(&mut buffer)?;
+    } else {
+        // plist writer repeats a single character, without indentation it writes one line
+        let indent = match is_compact {
+            true => String::new(),
+            false => formatting.indent("\t"),
+        };
+        let indent_char = indent.bytes().next().unwrap_or(b'\t');
+        let xml_options = XmlWriteOptions::default().indent(indent_char, indent.len());
+        plist.to_writer_xml_with_options(&mut buffer, &xml_options)?;
+    }
+    Ok(buffer)
+}
+
+/// `key` is the nearest dictionary key, items of arrays share key of the array.
+fn json_value_to_plist(
+    json: JsonValue,
+    key: Option<&str>,
+    options: &PlistDumpOptions,
+) -> Result<PlistValue> {
+    let plist = match json {
+        JsonValue::Null => bail!("Plist doesn't support null"),
+        JsonValue::Bool(b) => PlistValue::Boolean(b),
+        JsonValue::Number(n) => {
+            if let Some(i) = n.as_i64() {
+                PlistValue::from(i)
+            } else if let Some(u) = n.as_u64() {
+                PlistValue::from(u)
+            } else {
+                PlistValue::from(to_exact_f64(&n)?)
+            }
+        }
+        JsonValue::String(s) => match key {
+            Some(key) if options.data_keys.iter().any(|data_key| data_key == key) => {
+                match BASE64.decode(&s) {
+                    Ok(data) => PlistValue::Data(data),
+                    Err(err) => bail!("Plist data of key {key} is not base64: {err}"),
+                }
+            }
+            _ if options.dates => match Date::from_xml_format(&s) {
+                Ok(date) => PlistValue::Date(date),
+                Err(_) => PlistValue::String(s),
+            },
+            _ => PlistValue::String(s),
+        },
+        JsonValue::Array(items) => {
+            let items: Result<Vec<PlistValue>> = items
+                .into_iter()
+                .map(|item| json_value_to_plist(item, key, options))
+                .collect();
+            PlistValue::Array(items?)
+        }
+        JsonValue::Object(map) => match (number_double(&map), extjson_date(&map)) {
+            (Some(f), _) => PlistValue::Real(f),
+            (_, Some(date)) => PlistValue::Date(date),
+            _ => {
+                let mut dictionary = Dictionary::new();
+                for (key, value) in map {
+                    let value = json_value_to_plist(value, Some(&key), options)?;
+                    dictionary.insert(key, value);
+                }
+                PlistValue::Dictionary(dictionary)
+            }
+        },
+    };
+    Ok(plist)
+}
+
+/// `{"$date": "<iso-8601>"}` or `{"$date": {"$numberLong": "<millis>"}}`.
+fn extjson_date(map: &Map<String, JsonValue>) -> Option<Date> {
+    if map.len() != 1 {
+        return None;
+    }
+    match map.get("$date")? {
+        JsonValue::String(s) => Date::from_xml_format(s).ok(),
+        JsonValue::Object(millis) if millis.len() == 1 => {
+            let millis = millis.get("$numberLong")?.as_str()?.parse().ok()?;
+            let s = bson::DateTime::from_millis(millis)
+                .try_to_rfc3339_string()
+                .ok()?;
+            Date::from_xml_format(&s).ok()
+        }
+        _ => None,
+    }
+}
+
+fn plist_to_extjson(plist: &PlistValue) -> JsonValue {
+    match plist {
+        PlistValue::Array(items) => JsonValue::Array(items.iter().map(plist_to_extjson).collect()),
+        PlistValue::Dictionary(dictionary) => {
+            let map = dictionary
+                .iter()
+                .map(|(key, value)| (key.clone(), plist_to_extjson(value)))
+                .collect();
+            JsonValue::Object(map)
+        }
+        PlistValue::Boolean(b) => JsonValue::Bool(*b),
+        PlistValue::Data(data) => JsonValue::String(BASE64.encode(data)),
+        PlistValue::Date(date) => extjson("$date", date.to_xml_format()),
+        PlistValue::Real(f) => match Number::from_f64(*f) {
+            Some(n) => JsonValue::Number(n),
+            None => extjson("$numberDouble", f.to_string().replace("inf", "Infinity")),
+        },
+        PlistValue::Integer(i) => match i.as_signed() {
+            Some(i) => JsonValue::from(i),
+            None => JsonValue::from(i.as_unsigned()),
+        },
+        PlistValue::String(s) => JsonValue::String(s.clone()),
+        PlistValue::Uid(uid) => JsonValue::from(uid.get()),
+        _ => JsonValue::Null,
+    }
+}
+
+fn order_plist(plist: PlistValue, order: &KeyOrder) -> PlistValue {
+    match plist {
+        PlistValue::Array(items) => PlistValue::Array(
+            items
+                .into_iter()
+                .map(|item| order_plist(item, order))
+                .collect(),
+        ),
+        PlistValue::Dictionary(dictionary) => {
+            let mut entries: Vec<(String, PlistValue)> = dictionary
+                .into_iter()
+                .map(|(key, value)| (key, order_plist(value, order)))
+                .collect();
+            order.sort_entries(&mut entries, String::clone);
+            let mut dictionary = Dictionary::new();
+            for (key, value) in entries {
+                dictionary.insert(key, value);
+            }
+            PlistValue::Dictionary(dictionary)
+        }
+        plist => plist,
+    }
+}
@@ -0,0 +1,386 @@
+use std::collections::{HashMap, HashSet};
+use std::io::{Cursor, Read};
+
+use anyhow::{Context, Result, bail};
+use calamine::{Data, Range, Reader, Sheets, open_workbook_auto_from_rs};
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::{Reader as XmlReader, XmlVersion};
+use rust_xlsxwriter::{Format, Workbook};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value as JsonValue};
+use zip::ZipArchive;
+use zip::result::ZipError;
+
+use crate::csv_value::{CsvDumpOptions, format_cell, json_to_table};
+use crate::number::{is_integer, to_exact_f64};
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct SpreadsheetWrapper(serde_json::Value);
+
+/// Loads a sheet of xlsx, xls, xlsb or ods workbook as array of objects, using the first row as
+/// header. `sheet` is a sheet name or zero-based index, the first sheet is used by default.
+pub fn load_spreadsheet(input: &[u8], sheet: Option<&str>) -> Result<SpreadsheetWrapper> {
+    let mut workbook = open_workbook_auto_from_rs(Cursor::new(input))?;
+    let names = workbook.sheet_names();
+
+    let name = match sheet {
+        None => names.first(),
+        Some(sheet) => names.iter().find(|name| *name == sheet).or_else(|| {
+            let index = sheet.parse::<usize>().ok()?;
+            names.get(index)
+        }),
+    };
+    let Some(name) = name else {
+        bail!(
+            "Sheet {:?} not found, available: {names:?}",
+            sheet.unwrap_or("0")
+        );
+    };
+    let integer_cells = match workbook {
+        Sheets::Xlsx(_) => {
+            xlsx_integer_cells(input, name).context("Can't read number formats of xlsx workbook")?
+        }
+        _ => HashSet::new(),
+    };
+    let range = workbook.worksheet_range(name)?;
+
+    Ok(SpreadsheetWrapper(range_to_json(&range, &integer_cells)))
+}
+
+/// `integer_cells` are zero-based positions of number cells with an integer number format.
+fn range_to_json(range: &Range<Data>, integer_cells: &HashSet<(usize, usize)>) -> JsonValue {
+    let (start_row, start_col) = range.start().unwrap_or_default();
+    let mut rows = range.rows();
+    let Some(header_row) = rows.next() else {
+        return JsonValue::Array(vec![]);
+    };
+    let header = header_names(header_row);
+
+    let mut items = Vec::new();
+    for (index, row) in rows.enumerate() {
+        if row.iter().all(|cell| *cell == Data::Empty) {
+            continue;
+        }
+        let row_position = start_row as usize + index + 1;
+        let mut item = Map::new();
+        for (col, (key, cell)) in header.iter().zip(row).enumerate() {
+            let position = (row_position, start_col as usize + col);
+            item.insert(
+                key.clone(),
+                cell_to_json(cell, integer_cells.contains(&position)),
+            );
+        }
+        items.push(JsonValue::Object(item));
+    }
+    JsonValue::Array(items)
+}
+
+/// Keys of the header row, empty cells are named after their column and repeated names get
+/// `_2`, `_3`, ... suffixes so that columns don't overwrite each other.
+fn header_names(header_row: &[Data]) -> Vec<String> {
+    let mut used = HashSet::new();
+    let mut header = Vec::with_capacity(header_row.len());
+    for (index, cell) in header_row.iter().enumerate() {
+        let name = match cell {
+            Data::Empty => column_name(index),
+            cell => cell.to_string(),
+        };
+        let mut key = name.clone();
+        let mut count = 1;
+        while used.contains(&key) {
+            count += 1;
+            key = format!("{name}_{count}");
+        }
+        used.insert(key.clone());
+        header.push(key);
+    }
+    header
+}
+
+/// Spreadsheet column name like `A`, `B`, ..., `AA` for the zero-based index.
+fn column_name(mut index: usize) -> String {
+    let mut name = Vec::new();
+    loop {
+        name.push(b'A' + (index % 26) as u8);
+        if index < 26 {
+            break;
+        }
+        index = index / 26 - 1;
+    }
+    name.reverse();
+    String::from_utf8(name).unwrap_or_default()
+}
+
+/// Spreadsheets store every number as float, it becomes an integer only when the cell has an
+/// integer number format.
+fn cell_to_json(cell: &Data, is_integer_format: bool) -> JsonValue {
+    match cell {
+        Data::Empty => JsonValue::Null,
+        Data::Int(i) => JsonValue::from(*i),
+        Data::Float(f) if is_integer_format && f.fract() == 0.0 && f.abs() < i64::MAX as f64 => {
+            JsonValue::from(*f as i64)
+        }
+        Data::Float(f) => serde_json::Number::from_f64(*f)
+            .map(JsonValue::Number)
+            .unwrap_or(JsonValue::Null),
+        Data::Bool(b) => JsonValue::Bool(*b),
+        Data::String(s) | Data::DateTimeIso(s) | Data::DurationIso(s) => {
+            JsonValue::String(s.clone())
+        }
+        Data::DateTime(datetime) if datetime.is_duration() => {
+            let seconds = (datetime.as_f64() * 86400.0).round() as i64;
+            JsonValue::String(format!(
+                "{}:{:02}:{:02}",
+                seconds / 3600,
+                seconds / 60 % 60,
+                seconds % 60
+            ))
+        }
+        Data::DateTime(datetime) => {
+            let (year, month, day, hour, min, sec, milli) = datetime.to_ymd_hms_milli();
+            let date = format!("{year:04}-{month:02}-{day:02}");
+            let time = format!("{hour:02}:{min:02}:{sec:02}");
+            let value = match (datetime.as_f64() < 1.0, datetime.as_f64().fract() == 0.0) {
+                (true, _) => time,
+                (false, true) => date,
+                (false, false) if milli > 0 => format!("{date}T{time}.{milli:03}"),
+                (false, false) => format!("{date}T{time}"),
+            };
+            JsonValue::String(value)
+        }
+        Data::Error(error) => JsonValue::String(error.to_string()),
+    }
+}
+
+/// Zero-based positions of cells with an integer number format like `0` or `#,##0`. calamine
+/// reads every xlsx number as float and doesn't expose number formats, so styles of the sheet are
+/// read from the archive.
+fn xlsx_integer_cells(input: &[u8], sheet: &str) -> Result<HashSet<(usize, usize)>> {
+    let mut archive = ZipArchive::new(Cursor::new(input))?;
+    let styles = match read_part(&mut archive, "xl/styles.xml")? {
+        Some(styles) => xlsx_integer_styles(&styles)?,
+        None => Vec::new(),
+    };
+    let mut cells = HashSet::new();
+    if !styles.contains(&true) {
+        return Ok(cells);
+    }
+    let Some(path) = xlsx_sheet_path(&mut archive, sheet)? else {
+        return Ok(cells);
+    };
+    let Some(xml) = read_part(&mut archive, &path)? else {
+        return Ok(cells);
+    };
+
+    let mut reader = XmlReader::from_reader(xml.as_slice());
+    loop {
+        match reader.read_event()? {
+            Event::Start(e) | Event::Empty(e) if e.local_name().as_ref() == b"c" => {
+                let style = attribute(&e, b"s")?.and_then(|s| s.parse::<usize>().ok());
+                if style.is_some_and(|style| styles.get(style) == Some(&true))
+                    && let Some(position) = attribute(&e, b"r")?.as_deref().and_then(cell_position)
+                {
+                    cells.insert(position);
+                }
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    Ok(cells)
+}
+
+/// Whether each cell style (`cellXfs` item) of `xl/styles.xml` has an integer number format.
+fn xlsx_integer_styles(xml: &[u8]) -> Result<Vec<bool>> {
+    let mut custom_formats = HashMap::new();
+    let mut format_ids = Vec::new();
+    let mut is_cell_style = false;
+
+    let mut reader = XmlReader::from_reader(xml);
+    loop {
+        match reader.read_event()? {
+            Event::Start(e) | Event::Empty(e) => match e.local_name().as_ref() {
+                b"numFmt" => {
+                    if let (Some(id), Some(code)) =
+                        (attribute(&e, b"numFmtId")?, attribute(&e, b"formatCode")?)
+                    {
+                        custom_formats.insert(id, code);
+                    }
+                }
+                b"cellXfs" => is_cell_style = true,
+                b"xf" if is_cell_style => {
+                    format_ids.push(attribute(&e, b"numFmtId")?.unwrap_or_default());
+                }
+                _ => {}
+            },
+            Event::End(e) if e.local_name().as_ref() == b"cellXfs" => is_cell_style = false,
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    Ok(format_ids
+        .iter()
+        .map(|id| match custom_formats.get(id) {
+            Some(code) => is_integer_format(code),
+            // built-in `0`, `#,##0` and accounting formats without decimals
+            None => matches!(id.as_str(), "1" | "3" | "37" | "38"),
+        })
+        .collect())
+}
+
+/// Number format with digit placeholders and without decimals, percents, exponent, fractions
+/// or date parts, only the first section (for positive numbers) is checked.
+fn is_integer_format(code: &str) -> bool {
+    let section = code.split(';').next().unwrap_or_default();
+    let mut chars = section.chars();
+    let mut has_digits = false;
+    while let Some(c) = chars.next() {
+        match c {
+            // quoted text, colors and locales
+            '"' => _ = chars.by_ref().find(|c| *c == '"'),
+            '[' => _ = chars.by_ref().find(|c| *c == ']'),
+            // escaped character, space of character width and fill
+            '\\' | '_' | '*' => _ = chars.next(),
+            '0' | '#' | '?' => has_digits = true,
+            '.' | '%' | '/' | '@' => return false,
+            c if c.is_alphabetic() => return false,
+            _ => {}
+        }
+    }
+    has_digits
+}
+
+/// Path of sheet xml in the archive by the sheet name.
+fn xlsx_sheet_path(archive: &mut ZipArchive<Cursor<&[u8]>>, sheet: &str) -> Result<Option<String>> {
+    let Some(workbook) = read_part(archive, "xl/workbook.xml")? else {
+        return Ok(None);
+    };
+    let mut id = None;
+    let mut reader = XmlReader::from_reader(workbook.as_slice());
+    loop {
+        match reader.read_event()? {
+            Event::Start(e) | Event::Empty(e)
+                if e.local_name().as_ref() == b"sheet"
+                    && attribute(&e, b"name")?.as_deref() == Some(sheet) =>
+            {
+                id = attribute(&e, b"id")?;
+                break;
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    let Some(id) = id else {
+        return Ok(None);
+    };
+
+    let Some(relationships) = read_part(archive, "xl/_rels/workbook.xml.rels")? else {
+        return Ok(None);
+    };
+    let mut reader = XmlReader::from_reader(relationships.as_slice());
+    loop {
+        match reader.read_event()? {
+            Event::Start(e) | Event::Empty(e)
+                if e.local_name().as_ref() == b"Relationship"
+                    && attribute(&e, b"Id")?.as_deref() == Some(id.as_str()) =>
+            {
+                return Ok(attribute(&e, b"Target")?.map(|target| {
+                    match target.strip_prefix('/') {
+                        Some(absolute) => absolute.to_string(),
+                        None => format!("xl/{target}"),
+                    }
+                }));
+            }
+            Event::Eof => return Ok(None),
+            _ => {}
+        }
+    }
+}
+
+fn read_part(archive: &mut ZipArchive<Cursor<&[u8]>>, path: &str) -> Result<Option<Vec<u8>>> {
+    let mut file = match archive.by_name(path) {
+        Ok(file) => file,
+        Err(ZipError::FileNotFound) => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    let mut content = Vec::new();
+    file.read_to_end(&mut content)?;
+    Ok(Some(content))
+}
+
+/// Attribute value by local name, namespace prefix like `r:` is ignored.
+fn attribute(e: &BytesStart, name: &[u8]) -> Result<Option<String>> {
+    for attr in e.attributes() {
+        let attr = attr?;
+        if attr.key.local_name().as_ref() == name {
+            return Ok(Some(
+                attr.normalized_value(XmlVersion::Implicit1_0)?.into_owned(),
+            ));
+        }
+    }
+    Ok(None)
+}
+
+/// Zero-based row and column of a cell reference like `B12`.
+fn cell_position(reference: &str) -> Option<(usize, usize)> {
+    let split = reference.find(|c: char| c.is_ascii_digit())?;
+    let (letters, digits) = reference.split_at(split);
+    let col = letters.bytes().try_fold(0usize, |col, b| {
+        b.is_ascii_uppercase()
+            .then(|| col * 26 + usize::from(b - b'A') + 1)
+    })?;
+    let row = digits.parse::<usize>().ok()?;
+    Some((row.checked_sub(1)?, col.checked_sub(1)?))
+}
+
+pub fn json_to_xlsx(json: &[u8], sheet: Option<&str>, options: &CsvDumpOptions) -> Result<Vec<u8>> {
+    let table = json_to_table(json, options)?;
+
+    let mut workbook = Workbook::new();
+    let worksheet = workbook.add_worksheet();
+    if let Some(sheet) = sheet {
+        worksheet.set_name(sheet)?;
+    }
+
+    let columns = (0..table.header.len())
+        .map(|col| {
+            u16::try_from(col)
+                .with_context(|| format!("Column {col} is out of range of xlsx sheet"))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    // integers are written with `0` number format to be read back as integers
+    let integer_format = Format::new().set_num_format("0");
+
+    for (col, key) in columns.iter().zip(&table.header) {
+        worksheet.write_string(0, *col, key)?;
+    }
+    for (row, item) in table.rows.iter().enumerate() {
+        let row = u32::try_from(row + 1)
+            .with_context(|| format!("Row {row} is out of range of xlsx sheet"))?;
+        for (col, key) in columns.iter().copied().zip(&table.header) {
+            match item.get(key) {
+                None | Some(JsonValue::Null) => {}
+                Some(JsonValue::Bool(b)) => {
+                    worksheet.write_boolean(row, col, *b)?;
+                }
+                Some(JsonValue::Number(n)) if is_integer(n) => {
+                    worksheet.write_number_with_format(
+                        row,
+                        col,
+                        to_exact_f64(n)?,
+                        &integer_format,
+                    )?;
+                }
+                Some(JsonValue::Number(n)) => {
+                    worksheet.write_number(row, col, to_exact_f64(n)?)?;
+                }
+                Some(value) => {
+                    worksheet.write_string(row, col, format_cell(value)?)?;
+                }
+            }
+        }
+    }
+
+    Ok(workbook.save_to_buffer()?)
+}
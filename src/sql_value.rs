@@ -0,0 +1,188 @@
+use anyhow::{Result, bail};
+use serde_json::Value as JsonValue;
+
+use crate::csv_value::{CsvDumpOptions, Table, format_cell, json_to_table};
+use crate::formatting::Formatting;
+use crate::number::is_integer;
+
+#[derive(Debug, Copy, Clone, Default, PartialEq, clap::ValueEnum)]
+pub enum SqlDialect {
+    #[default]
+    Sqlite,
+    Postgres,
+    Mysql,
+}
+
+#[derive(Debug, Clone)]
+pub struct SqlDumpOptions {
+    pub dialect: SqlDialect,
+    pub table: String,
+    /// Dump `CREATE TABLE` statement before inserts
+    pub create_table: bool,
+}
+
+impl Default for SqlDumpOptions {
+    fn default() -> Self {
+        Self {
+            dialect: SqlDialect::default(),
+            table: "data".to_string(),
+            create_table: true,
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+enum ColumnKind {
+    Null,
+    Bool,
+    Integer,
+    Float,
+    Text,
+    Json,
+}
+
+impl ColumnKind {
+    fn of(value: &JsonValue) -> Self {
+        match value {
+            JsonValue::Null => ColumnKind::Null,
+            JsonValue::Bool(_) => ColumnKind::Bool,
+            JsonValue::Number(n) if n.is_f64() => ColumnKind::Float,
+            JsonValue::Number(_) => ColumnKind::Integer,
+            JsonValue::String(_) => ColumnKind::Text,
+            JsonValue::Array(_) | JsonValue::Object(_) => ColumnKind::Json,
+        }
+    }
+
+    fn merge(self, other: Self) -> Self {
+        match (self, other) {
+            (a, b) if a == b => a,
+            (ColumnKind::Null, kind) | (kind, ColumnKind::Null) => kind,
+            (ColumnKind::Integer, ColumnKind::Float) | (ColumnKind::Float, ColumnKind::Integer) => {
+                ColumnKind::Float
+            }
+            _ => ColumnKind::Text,
+        }
+    }
+
+    fn sql_type(self, dialect: SqlDialect) -> &'static str {
+        match (self, dialect) {
+            (ColumnKind::Bool, _) => "BOOLEAN",
+            (ColumnKind::Integer, SqlDialect::Sqlite) => "INTEGER",
+            (ColumnKind::Integer, _) => "BIGINT",
+            (ColumnKind::Float, SqlDialect::Sqlite) => "REAL",
+            (ColumnKind::Float, SqlDialect::Postgres) => "DOUBLE PRECISION",
+            (ColumnKind::Float, SqlDialect::Mysql) => "DOUBLE",
+            (ColumnKind::Json, SqlDialect::Postgres) => "JSONB",
+            (ColumnKind::Json, SqlDialect::Mysql) => "JSON",
+            (ColumnKind::Null | ColumnKind::Text | ColumnKind::Json, _) => "TEXT",
+        }
+    }
+}
+
+/// Dumps array of objects as `CREATE TABLE` with inferred column types and `INSERT` statements.
+/// Statements end with line ending of `formatting`, line breaks of string literals are kept.
+pub fn json_to_sql(
+    json: &[u8],
+    is_compact: bool,
+    options: &SqlDumpOptions,
+    csv_options: &CsvDumpOptions,
+    formatting: &Formatting,
+) -> Result<Vec<u8>> {
+    let table = json_to_table(json, csv_options)?;
+    if table.header.is_empty() {
+        bail!("Sql table needs columns, input has no keys");
+    }
+    let dialect = options.dialect;
+    let line_ending = formatting.line_ending.as_str();
+
+    let mut buffer = String::new();
+    if options.create_table {
+        let statement = create_table_statement(&table, options, is_compact, line_ending);
+        buffer.push_str(&statement);
+        buffer.push_str(line_ending);
+    }
+
+    let table_name = quote_identifier(&options.table, dialect);
+    let columns: Vec<String> = table
+        .header
+        .iter()
+        .map(|column| quote_identifier(column, dialect))
+        .collect();
+    let columns = columns.join(", ");
+    for row in &table.rows {
+        let mut values = Vec::with_capacity(table.header.len());
+        for key in &table.header {
+            values.push(match row.get(key) {
+                Some(value) => quote_value(value, dialect)?,
+                None => "NULL".to_string(),
+            });
+        }
+        buffer.push_str(&format!(
+            "INSERT INTO {table_name} ({columns}) VALUES ({});{line_ending}",
+            values.join(", ")
+        ));
+    }
+    Ok(buffer.into_bytes())
+}
+
+pub(crate) fn create_table_statement(
+    table: &Table,
+    options: &SqlDumpOptions,
+    is_compact: bool,
+    line_ending: &str,
+) -> String {
+    let dialect = options.dialect;
+    let table_name = quote_identifier(&options.table, dialect);
+    let definitions: Vec<String> = table
+        .header
+        .iter()
+        .map(|key| {
+            let kind = table
+                .rows
+                .iter()
+                .filter_map(|row| row.get(key))
+                .map(ColumnKind::of)
+                .fold(ColumnKind::Null, ColumnKind::merge);
+            let column = quote_identifier(key, dialect);
+            format!("{column} {}", kind.sql_type(dialect))
+        })
+        .collect();
+
+    if is_compact {
+        format!("CREATE TABLE {table_name} ({});", definitions.join(", "))
+    } else {
+        format!(
+            "CREATE TABLE {table_name} ({line_ending}  {}{line_ending});",
+            definitions.join(&format!(",{line_ending}  "))
+        )
+    }
+}
+
+pub(crate) fn quote_identifier(name: &str, dialect: SqlDialect) -> String {
+    match dialect {
+        SqlDialect::Mysql => format!("`{}`", name.replace('`', "``")),
+        SqlDialect::Sqlite | SqlDialect::Postgres => format!("\"{}\"", name.replace('"', "\"\"")),
+    }
+}
+
+fn quote_value(value: &JsonValue, dialect: SqlDialect) -> Result<String> {
+    let quoted = match (value, dialect) {
+        (JsonValue::Null, _) => "NULL".to_string(),
+        (JsonValue::Bool(b), SqlDialect::Sqlite) => u8::from(*b).to_string(),
+        (JsonValue::Bool(b), _) => b.to_string().to_uppercase(),
+        (JsonValue::Number(n), _) if is_integer(n) && n.as_i64().is_none() => {
+            bail!("Integer {n} is out of range of sql integer columns")
+        }
+        (JsonValue::Number(n), _) => n.to_string(),
+        (value, SqlDialect::Mysql) => {
+            let s = format_cell(value)?
+                .replace('\\', "\\\\")
+                .replace('\'', "''");
+            format!("'{s}'")
+        }
+        (value, SqlDialect::Sqlite | SqlDialect::Postgres) => {
+            format!("'{}'", format_cell(value)?.replace('\'', "''"))
+        }
+    };
+    Ok(quoted)
+}
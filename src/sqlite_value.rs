@@ -0,0 +1,149 @@
+use anyhow::{Context, Result, bail};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use rusqlite::types::{Value as SqlValue, ValueRef};
+use rusqlite::{Connection, MAIN_DB, params_from_iter};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value as JsonValue};
+
+use crate::csv_value::{CsvDumpOptions, format_cell, json_to_table};
+use crate::number::to_exact_f64;
+use crate::sql_value::{SqlDialect, SqlDumpOptions, create_table_statement, quote_identifier};
+
+#[derive(Debug, Deserialize, Serialize)]
+pub struct SqliteWrapper(serde_json::Value);
+
+#[derive(Debug, Clone, Default)]
+pub struct SqliteLoadOptions {
+    /// Table to read, may be omitted when database has a single table
+    pub table: Option<String>,
+    /// `SELECT` query to read instead of the whole table
+    pub query: Option<String>,
+}
+
+/// Loads rows of sqlite table or query result as array of objects. Blobs are encoded as base64.
+pub fn load_sqlite(input: &[u8], options: &SqliteLoadOptions) -> Result<SqliteWrapper> {
+    let mut connection = Connection::open_in_memory()?;
+    connection.deserialize_read_exact(MAIN_DB, input, input.len(), true)?;
+
+    let query = match (&options.query, &options.table) {
+        (Some(query), _) => query.clone(),
+        (None, Some(table)) => format!(
+            "SELECT * FROM {}",
+            quote_identifier(table, SqlDialect::Sqlite)
+        ),
+        (None, None) => {
+            let tables = table_names(&connection)?;
+            let [table] = tables.as_slice() else {
+                bail!("Sqlite table must be specified, available: {tables:?}");
+            };
+            format!(
+                "SELECT * FROM {}",
+                quote_identifier(table, SqlDialect::Sqlite)
+            )
+        }
+    };
+
+    let mut statement = connection.prepare(&query)?;
+    let columns: Vec<String> = statement
+        .column_names()
+        .into_iter()
+        .map(str::to_string)
+        .collect();
+    let mut rows = statement.query([])?;
+    let mut items = Vec::new();
+    while let Some(row) = rows.next()? {
+        let mut item = Map::new();
+        for (index, column) in columns.iter().enumerate() {
+            let value = to_json(row.get_ref(index)?)
+                .with_context(|| format!("Column {column} of row {} is invalid", items.len()))?;
+            item.insert(column.clone(), value);
+        }
+        items.push(JsonValue::Object(item));
+    }
+    Ok(SqliteWrapper(JsonValue::Array(items)))
+}
+
+fn table_names(connection: &Connection) -> Result<Vec<String>> {
+    let mut statement = connection.prepare(
+        "SELECT name FROM sqlite_schema WHERE type = 'table' AND name NOT LIKE 'sqlite_%' ORDER BY name",
+    )?;
+    let names = statement.query_map([], |row| row.get(0))?;
+    Ok(names.collect::<rusqlite::Result<_>>()?)
+}
+
+fn to_json(value: ValueRef) -> Result<JsonValue> {
+    let value = match value {
+        ValueRef::Null => JsonValue::Null,
+        ValueRef::Integer(i) => JsonValue::from(i),
+        ValueRef::Real(f) => match serde_json::Number::from_f64(f) {
+            Some(n) => JsonValue::Number(n),
+            None => bail!("Real {f} can't be loaded, sqlite input has no NaN and infinity"),
+        },
+        ValueRef::Text(text) => JsonValue::String(std::str::from_utf8(text)?.to_string()),
+        ValueRef::Blob(blob) => JsonValue::String(BASE64.encode(blob)),
+    };
+    Ok(value)
+}
+
+/// Dumps array of objects into a new table of in-memory sqlite database and returns its file.
+/// Sqlite has no booleans, they are stored as 0 and 1 and loaded back as integers.
+pub fn json_to_sqlite(
+    json: &[u8],
+    options: &SqlDumpOptions,
+    csv_options: &CsvDumpOptions,
+) -> Result<Vec<u8>> {
+    let table = json_to_table(json, csv_options)?;
+    if table.header.is_empty() {
+        bail!("Sqlite table needs columns, input has no keys");
+    }
+    let options = SqlDumpOptions {
+        dialect: SqlDialect::Sqlite,
+        ..options.clone()
+    };
+
+    let mut connection = Connection::open_in_memory()?;
+    let transaction = connection.transaction()?;
+    transaction.execute(&create_table_statement(&table, &options, false, "\n"), [])?;
+
+    let columns: Vec<String> = table
+        .header
+        .iter()
+        .map(|column| quote_identifier(column, SqlDialect::Sqlite))
+        .collect();
+    let placeholders = vec!["?"; columns.len()].join(", ");
+    let insert = format!(
+        "INSERT INTO {} ({}) VALUES ({placeholders})",
+        quote_identifier(&options.table, SqlDialect::Sqlite),
+        columns.join(", ")
+    );
+    {
+        let mut statement = transaction.prepare(&insert)?;
+        for row in &table.rows {
+            let mut values = Vec::with_capacity(table.header.len());
+            for key in &table.header {
+                values.push(match row.get(key) {
+                    Some(value) => to_sql(value)?,
+                    None => SqlValue::Null,
+                });
+            }
+            statement.execute(params_from_iter(values))?;
+        }
+    }
+    transaction.commit()?;
+
+    Ok(connection.serialize(MAIN_DB)?.to_vec())
+}
+
+fn to_sql(value: &JsonValue) -> Result<SqlValue> {
+    let value = match value {
+        JsonValue::Null => SqlValue::Null,
+        JsonValue::Bool(b) => SqlValue::Integer(i64::from(*b)),
+        JsonValue::Number(n) => match n.as_i64() {
+            Some(i) => SqlValue::Integer(i),
+            None => SqlValue::Real(to_exact_f64(n)?),
+        },
+        value => SqlValue::Text(format_cell(value)?),
+    };
+    Ok(value)
+}
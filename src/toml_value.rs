@@ -0,0 +1,380 @@
+use anyhow::{Context, Result, anyhow, bail};
+use serde::{Serialize, Serializer};
+use serde_json::{Number, Value as JsonValue};
+use toml::value::Datetime;
+use toml_edit::{ArrayOfTables, DocumentMut, InlineTable, Item, Key, Table, Value as EditValue};
+
+use crate::bson_value::{extjson, json_to_bson};
+use crate::formatting::{Formatting, InlineWidth};
+use crate::key_order::KeyOrder;
+use crate::number::to_exact_f64;
+
+#[derive(Debug, Clone, Default)]
+pub struct TomlDumpOptions {
+    /// Strings with a date like yaml timestamps are written as toml datetimes, extended json
+    /// dates of bson and dates of plist always are
+    pub datetimes: bool,
+    /// Key of table wrapping top-level arrays and other values which aren't tables
+    pub root_key: Option<String>,
+    /// Tables nested deeper than this are written as inline tables, top-level keys have depth 1
+    pub inline_depth: Option<usize>,
+    /// Tables and arrays of tables which fit into this number of characters are written inline
+    pub inline_size: Option<usize>,
+}
+
+impl TomlDumpOptions {
+    fn is_inline(&self, width: &InlineWidth, depth: usize) -> bool {
+        self.inline_depth.is_some_and(|max_depth| depth > max_depth)
+            || self
+                .inline_size
+                .is_some_and(|max_size| width.width <= max_size)
+    }
+}
+
+/// Datetimes are serialized as strings like `1979-05-27T07:32:00Z`.
+#[derive(Debug)]
+pub struct TomlWrapper(toml::Value);
+
+impl TomlWrapper {
+    pub(crate) fn with_key_order(&self, order: &KeyOrder) -> Self {
+        TomlWrapper(order_toml(self.0.clone(), order))
+    }
+
+    /// Offset datetimes become extended json dates, other datetimes are kept as strings.
+    pub(crate) fn to_extjson(&self) -> JsonValue {
+        toml_to_extjson(&self.0)
+    }
+}
+
+impl Serialize for TomlWrapper {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        Typed(&self.0).serialize(serializer)
+    }
+}
+
+struct Typed<'a>(&'a toml::Value);
+
+impl Serialize for Typed<'_> {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self.0 {
+            toml::Value::Array(items) => serializer.collect_seq(items.iter().map(Typed)),
+            toml::Value::Table(table) => {
+                serializer.collect_map(table.iter().map(|(key, value)| (key, Typed(value))))
+            }
+            toml::Value::Datetime(datetime) => serializer.collect_str(datetime),
+            value => value.serialize(serializer),
+        }
+    }
+}
+
+/// Floats are parsed into f64, literals which it would round are errors.
+pub fn load_toml(input: &[u8]) -> Result<TomlWrapper> {
+    let s = std::str::from_utf8(input)?;
+    let toml = toml::from_str(s)?;
+    let document: DocumentMut = s.parse()?;
+    check_floats(document.as_table(), "")?;
+    Ok(TomlWrapper(toml))
+}
+
+fn check_floats(table: &Table, path: &str) -> Result<()> {
+    table.iter().try_for_each(|(key, item)| {
+        let path = key_path(path, key);
+        match item {
+            Item::Value(value) => check_float_values(value, &path),
+            Item::Table(table) => check_floats(table, &path),
+            Item::ArrayOfTables(tables) => tables
+                .iter()
+                .enumerate()
+                .try_for_each(|(i, table)| check_floats(table, &key_path(&path, &i.to_string()))),
+            Item::None => Ok(()),
+        }
+    })
+}
+
+fn check_float_values(value: &EditValue, path: &str) -> Result<()> {
+    match value {
+        EditValue::Float(f) => {
+            let raw = f.as_repr().and_then(|repr| repr.as_raw().as_str());
+            let Some(raw) = raw else {
+                return Ok(());
+            };
+            let digits = raw.replace('_', "");
+            // inf and nan aren't numbers of json
+            if let Ok(n) = digits.trim_start_matches('+').parse::<Number>() {
+                to_exact_f64(&n).with_context(|| format!("Float at {path} can't be loaded"))?;
+            }
+            Ok(())
+        }
+        EditValue::Array(items) => items
+            .iter()
+            .enumerate()
+            .try_for_each(|(i, item)| check_float_values(item, &key_path(path, &i.to_string()))),
+        EditValue::InlineTable(table) => table
+            .iter()
+            .try_for_each(|(key, value)| check_float_values(value, &key_path(path, key))),
+        _ => Ok(()),
+    }
+}
+
+fn key_path(path: &str, key: &str) -> String {
+    match path {
+        "" => key.to_string(),
+        path => format!("{path}.{key}"),
+    }
+}
+
+pub fn dump_toml(
+    toml: &TomlWrapper,
+    is_compact: bool,
+    options: &TomlDumpOptions,
+    formatting: &Formatting,
+) -> Result<Vec<u8>> {
+    write_toml(serialize_toml(&toml.0)?, is_compact, options, formatting)
+}
+
+/// Extended json dates of bson become datetimes, strings with a date only with `datetimes`.
+pub(crate) fn value_to_toml<T: Serialize>(
+    value: &T,
+    is_compact: bool,
+    options: &TomlDumpOptions,
+    formatting: &Formatting,
+) -> Result<Vec<u8>> {
+    let mut toml = serialize_toml(value)?;
+    typed_datetimes(&mut toml, options.datetimes);
+    write_toml(toml, is_compact, options, formatting)
+}
+
+/// Offset datetimes become bson datetimes, other datetimes are kept as strings.
+pub fn toml_to_bson(toml: &TomlWrapper) -> Result<Vec<u8>> {
+    let json = serde_json::to_vec(&toml.to_extjson())?;
+    json_to_bson(&json)
+}
+
+/// Errors point to the path of value which can't be written.
+fn serialize_toml<T: Serialize>(value: &T) -> Result<EditValue> {
+    serde_path_to_error::serialize(value, toml_edit::ser::ValueSerializer::new()).map_err(|err| {
+        match err.path().iter().next() {
+            Some(_) => anyhow!(
+                "Value at {} can't be written in toml: {}",
+                err.path(),
+                err.inner()
+            ),
+            None => anyhow!("Value can't be written in toml: {}", err.inner()),
+        }
+    })
+}
+
+/// Values of tables are written before nested tables, so keys of nested tables go last.
+fn write_toml(
+    toml: EditValue,
+    is_compact: bool,
+    options: &TomlDumpOptions,
+    formatting: &Formatting,
+) -> Result<Vec<u8>> {
+    let root = match (toml, &options.root_key) {
+        (EditValue::InlineTable(table), _) => table,
+        (toml, Some(key)) => InlineTable::from_iter([(key.as_str(), toml)]),
+        (toml, None) => bail!(
+            "Root value of toml must be a table, {} can be written under a root key",
+            toml.type_name()
+        ),
+    };
+    let width = match options.inline_size {
+        Some(_) => table_width(&root),
+        None => InlineWidth::default(),
+    };
+    let mut root = layout_table(root, width, 0, options);
+    if !is_compact {
+        pretty_arrays(&mut root, &formatting.indent("    "), formatting);
+    }
+    Ok(DocumentMut::from(root).to_string().into_bytes())
+}
+
+/// Width of value written inline, widths of nested tables are computed once instead of writing
+/// them on every level of layout. Widths aren't needed without `inline_size`.
+fn toml_width(value: &EditValue) -> InlineWidth {
+    match value {
+        EditValue::Array(items) => {
+            InlineWidth::join(items.iter().map(toml_width).collect(), "[]".len(), 0)
+        }
+        EditValue::InlineTable(table) if !table.is_empty() => table_width(table),
+        value => InlineWidth::scalar(value.to_string().trim()),
+    }
+}
+
+fn table_width(table: &InlineTable) -> InlineWidth {
+    let mut keys = 0;
+    let mut items = Vec::with_capacity(table.len());
+    for (key, value) in table {
+        keys += Key::new(key).display_repr().chars().count() + " = ".len();
+        items.push(toml_width(value));
+    }
+    InlineWidth::join(items, "{  }".len(), keys)
+}
+
+/// Chooses `[table]` and `[[array]]` headers or inline tables for values of table at `depth`.
+fn layout_table(
+    table: InlineTable,
+    width: InlineWidth,
+    depth: usize,
+    options: &TomlDumpOptions,
+) -> Table {
+    let mut layout = Table::new();
+    let mut widths = width.items.into_iter();
+    for (key, value) in table {
+        let width = widths.next().unwrap_or_default();
+        let is_inline = options.is_inline(&width, depth + 1);
+        let item = match value {
+            EditValue::InlineTable(table) if !is_inline => {
+                Item::Table(layout_table(table, width, depth + 1, options))
+            }
+            EditValue::Array(items)
+                if !is_inline
+                    && !items.is_empty()
+                    && items.iter().all(EditValue::is_inline_table) =>
+            {
+                let mut widths = width.items.into_iter();
+                let tables = items.into_iter().filter_map(|item| {
+                    let width = widths.next().unwrap_or_default();
+                    match item {
+                        EditValue::InlineTable(table) => {
+                            Some(layout_table(table, width, depth + 1, options))
+                        }
+                        _ => None,
+                    }
+                });
+                Item::ArrayOfTables(ArrayOfTables::from_iter(tables))
+            }
+            value => Item::Value(value),
+        };
+        layout.insert(&key, item);
+    }
+    // headers of tables with nested tables only are omitted, empty tables keep them
+    layout.set_implicit(!layout.is_empty());
+    layout
+}
+
+/// Items of arrays are written on separate lines unless they fit into max width, arrays nested
+/// into values stay compact.
+fn pretty_arrays(table: &mut Table, indent: &str, formatting: &Formatting) {
+    for (key, item) in table.iter_mut() {
+        match item {
+            Item::Value(EditValue::Array(items)) if items.len() > 1 => {
+                let width = key.get().chars().count() + " = ".len();
+                if formatting.fits(width + items.to_string().trim().chars().count()) {
+                    continue;
+                }
+                for item in items.iter_mut() {
+                    item.decor_mut().set_prefix(format!("\n{indent}"));
+                }
+                items.set_trailing("\n");
+                items.set_trailing_comma(true);
+            }
+            Item::Table(table) => pretty_arrays(table, indent, formatting),
+            Item::ArrayOfTables(tables) => {
+                for table in tables.iter_mut() {
+                    pretty_arrays(table, indent, formatting);
+                }
+            }
+            _ => (),
+        }
+    }
+}
+
+fn typed_datetimes(toml: &mut EditValue, is_string_parsed: bool) {
+    match toml {
+        EditValue::String(s) if is_string_parsed => {
+            if let Some(datetime) = parse_datetime(s.value()) {
+                *toml = EditValue::from(datetime);
+            }
+        }
+        EditValue::Array(items) => items
+            .iter_mut()
+            .for_each(|item| typed_datetimes(item, is_string_parsed)),
+        EditValue::InlineTable(table) => {
+            if let Some(datetime) = extjson_date(table) {
+                *toml = EditValue::from(datetime);
+            } else {
+                table
+                    .iter_mut()
+                    .for_each(|(_, value)| typed_datetimes(value, is_string_parsed));
+            }
+        }
+        _ => (),
+    }
+}
+
+/// Datetimes without a date like `07:32:00` are too ambiguous to be converted.
+fn parse_datetime(s: &str) -> Option<Datetime> {
+    s.parse::<Datetime>()
+        .ok()
+        .filter(|datetime| datetime.date.is_some())
+}
+
+/// `{"$date": "<iso-8601>"}` or `{"$date": {"$numberLong": "<millis>"}}`.
+fn extjson_date(table: &InlineTable) -> Option<Datetime> {
+    if table.len() != 1 {
+        return None;
+    }
+    match table.get("$date")? {
+        EditValue::String(s) => parse_datetime(s.value()),
+        EditValue::InlineTable(millis) if millis.len() == 1 => {
+            let millis = millis.get("$numberLong")?.as_str()?.parse().ok()?;
+            let s = bson::DateTime::from_millis(millis)
+                .try_to_rfc3339_string()
+                .ok()?;
+            parse_datetime(&s)
+        }
+        _ => None,
+    }
+}
+
+fn toml_to_extjson(toml: &toml::Value) -> JsonValue {
+    match toml {
+        toml::Value::String(s) => JsonValue::String(s.clone()),
+        toml::Value::Integer(i) => JsonValue::from(*i),
+        toml::Value::Float(f) => match Number::from_f64(*f) {
+            Some(n) => JsonValue::Number(n),
+            None => extjson("$numberDouble", f.to_string().replace("inf", "Infinity")),
+        },
+        toml::Value::Boolean(b) => JsonValue::Bool(*b),
+        toml::Value::Datetime(datetime) if datetime.offset.is_some() => {
+            extjson("$date", datetime.to_string())
+        }
+        toml::Value::Datetime(datetime) => JsonValue::String(datetime.to_string()),
+        toml::Value::Array(items) => JsonValue::Array(items.iter().map(toml_to_extjson).collect()),
+        toml::Value::Table(table) => {
+            let map = table
+                .iter()
+                .map(|(key, value)| (key.clone(), toml_to_extjson(value)))
+                .collect();
+            JsonValue::Object(map)
+        }
+    }
+}
+
+fn order_toml(toml: toml::Value, order: &KeyOrder) -> toml::Value {
+    match toml {
+        toml::Value::Array(items) => toml::Value::Array(
+            items
+                .into_iter()
+                .map(|item| order_toml(item, order))
+                .collect(),
+        ),
+        toml::Value::Table(table) => {
+            let mut entries: Vec<(String, toml::Value)> = table
+                .into_iter()
+                .map(|(key, value)| (key, order_toml(value, order)))
+                .collect();
+            order.sort_entries(&mut entries, String::clone);
+            toml::Value::Table(entries.into_iter().collect())
+        }
+        toml => toml,
+    }
+}
@@ -0,0 +1,451 @@
+use std::collections::HashMap;
+
+use anyhow::{Result, bail};
+use serde_json::Value as JsonValue;
+
+use crate::csv_value::ColumnType;
+use crate::xml_value::{XmlElement, XmlLoadOptions, XmlNode, parse_xml};
+
+/// Types and cardinality of xml elements and attributes from XML Schema (XSD). Declarations are
+/// looked up by local name in content of the parent element, so elements of the same name may
+/// have different types under different parents.
+#[derive(Debug, Clone, Default)]
+pub struct XmlSchema {
+    /// Declarations by context and local name, top-level elements have an empty context
+    elements: HashMap<(String, String), ElementDeclaration>,
+    /// Types of attributes by context of their element and local name
+    attributes: HashMap<(String, String), SchemaType>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ElementDeclaration {
+    element_type: Option<SchemaType>,
+    /// Element may occur more than once
+    is_array: bool,
+    /// Context of declarations of children and attributes, named complex types are shared by
+    /// their elements
+    content: String,
+}
+
+impl ElementDeclaration {
+    pub(crate) fn element_type(&self) -> Option<SchemaType> {
+        self.element_type
+    }
+
+    pub(crate) fn is_array(&self) -> bool {
+        self.is_array
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub(crate) struct SchemaType {
+    item_type: ColumnType,
+    /// Whitespace separated list of items
+    is_list: bool,
+}
+
+impl SchemaType {
+    fn item(item_type: ColumnType) -> Self {
+        Self {
+            item_type,
+            is_list: false,
+        }
+    }
+
+    /// Converts text into value of this type, `None` if it doesn't match the type.
+    pub(crate) fn convert(self, s: &str) -> Option<JsonValue> {
+        if self.is_list {
+            let items: Option<Vec<JsonValue>> = s
+                .split_whitespace()
+                .map(|item| convert_item(item, self.item_type))
+                .collect();
+            return items.map(JsonValue::Array);
+        }
+        if s.trim().is_empty() && self.item_type != ColumnType::String {
+            return Some(JsonValue::Null);
+        }
+        convert_item(s, self.item_type)
+    }
+}
+
+fn convert_item(s: &str, item_type: ColumnType) -> Option<JsonValue> {
+    match (item_type, s.trim()) {
+        (ColumnType::String, _) => item_type.convert(s),
+        // xsd booleans may also be written as digits
+        (ColumnType::Bool, "1") => Some(JsonValue::Bool(true)),
+        (ColumnType::Bool, "0") => Some(JsonValue::Bool(false)),
+        // special floats of xsd have no json numbers
+        (ColumnType::Float, "INF" | "+INF" | "-INF" | "NaN") => Some(JsonValue::String(s.into())),
+        (ColumnType::Datetime, s) => item_type.convert(s).or_else(|| convert_zoned(s)),
+        (item_type, s) => item_type.convert(s),
+    }
+}
+
+/// Xsd dates and times may have a timezone like `2002-09-24Z` or `13:20:00-05:00`, such values
+/// are kept as strings.
+fn convert_zoned(s: &str) -> Option<JsonValue> {
+    let local = match s.strip_suffix('Z') {
+        Some(local) => local,
+        None => {
+            let (local, offset) = s.split_at_checked(s.len().checked_sub(6)?)?;
+            let offset = offset.as_bytes();
+            let is_offset = matches!(offset[0], b'+' | b'-')
+                && offset[3] == b':'
+                && [1, 2, 4, 5].iter().all(|&i| offset[i].is_ascii_digit());
+            is_offset.then_some(local)?
+        }
+    };
+    ColumnType::Datetime
+        .convert(local)
+        .map(|_| JsonValue::String(s.to_string()))
+}
+
+impl XmlSchema {
+    pub fn parse(xsd: &[u8]) -> Result<Self> {
+        let document = parse_xml(xsd, &XmlLoadOptions::default())?;
+        let Some(root) = document.iter().find_map(as_element) else {
+            bail!("Can't read xml schema");
+        };
+        if local_name(&root.name) != "schema" {
+            bail!("Xml schema root must be <schema>, found <{}>", root.name);
+        }
+
+        let mut definitions = Definitions::default();
+        definitions.collect(root);
+        let mut schema = XmlSchema::default();
+        for child in root.children.iter().filter_map(as_element) {
+            match (local_name(&child.name), attribute(child, "name")) {
+                ("element", _) => definitions.declare_element(child, "", false, &mut schema),
+                ("attribute", _) => definitions.declare_attribute(child, "", &mut schema),
+                ("complexType", Some(name)) => {
+                    definitions.declare(child, &type_context(name), false, 0, &mut schema)
+                }
+                _ => (),
+            }
+        }
+        Ok(schema)
+    }
+
+    /// Declaration of element in content of its parent, top-level elements have no parent.
+    /// Elements which parent doesn't declare, like ones allowed by `<any>`, fall back to
+    /// top-level declarations.
+    pub(crate) fn element(
+        &self,
+        parent: Option<&ElementDeclaration>,
+        name: &str,
+    ) -> Option<&ElementDeclaration> {
+        let name = local_name(name).to_string();
+        parent
+            .and_then(|parent| self.elements.get(&(parent.content.clone(), name.clone())))
+            .or_else(|| self.elements.get(&(String::new(), name)))
+    }
+
+    pub(crate) fn attribute_type(
+        &self,
+        element: Option<&ElementDeclaration>,
+        name: &str,
+    ) -> Option<SchemaType> {
+        let name = local_name(name).to_string();
+        element
+            .and_then(|element| {
+                self.attributes
+                    .get(&(element.content.clone(), name.clone()))
+            })
+            .or_else(|| self.attributes.get(&(String::new(), name)))
+            .copied()
+    }
+}
+
+/// Named definitions of schema, which are referenced by declarations.
+#[derive(Default)]
+struct Definitions<'a> {
+    simple_types: HashMap<&'a str, &'a XmlElement>,
+    complex_types: HashMap<&'a str, &'a XmlElement>,
+    groups: HashMap<&'a str, &'a XmlElement>,
+    attribute_groups: HashMap<&'a str, &'a XmlElement>,
+    elements: HashMap<&'a str, &'a XmlElement>,
+    attributes: HashMap<&'a str, &'a XmlElement>,
+}
+
+impl<'a> Definitions<'a> {
+    /// Named definitions are children of `<schema>`.
+    fn collect(&mut self, schema: &'a XmlElement) {
+        for child in schema.children.iter().filter_map(as_element) {
+            let Some(name) = attribute(child, "name") else {
+                continue;
+            };
+            let definitions = match local_name(&child.name) {
+                "simpleType" => &mut self.simple_types,
+                "complexType" => &mut self.complex_types,
+                "group" => &mut self.groups,
+                "attributeGroup" => &mut self.attribute_groups,
+                "element" => &mut self.elements,
+                "attribute" => &mut self.attributes,
+                _ => continue,
+            };
+            definitions.insert(name, child);
+        }
+    }
+
+    /// Records declaration of element in `context`, `is_repeated` is set inside of sequences
+    /// and choices which may occur more than once. Referenced elements keep their type and
+    /// content but get cardinality of the reference.
+    fn declare_element(
+        &self,
+        element: &XmlElement,
+        context: &str,
+        is_repeated: bool,
+        schema: &mut XmlSchema,
+    ) {
+        let (name, definition, is_reference) =
+            match (attribute(element, "name"), attribute(element, "ref")) {
+                (Some(name), _) => (name, Some(element), false),
+                (None, Some(reference)) => {
+                    let name = local_name(reference);
+                    (name, self.elements.get(name).copied(), true)
+                }
+                (None, None) => return,
+            };
+        let content = match definition.and_then(|definition| attribute(definition, "type")) {
+            Some(type_name) => type_context(local_name(type_name)),
+            // anonymous types of referenced elements are declared with top-level elements
+            None if is_reference => format!("/{name}"),
+            None => format!("{context}/{name}"),
+        };
+        let declaration = ElementDeclaration {
+            element_type: definition.and_then(|definition| self.element_type(definition)),
+            is_array: is_repeated || max_occurs(element) > 1,
+            content: content.clone(),
+        };
+        let key = (context.to_string(), name.to_string());
+        match schema.elements.get_mut(&key) {
+            Some(previous) => previous.is_array |= declaration.is_array,
+            None => {
+                schema.elements.insert(key, declaration);
+            }
+        }
+
+        if !is_reference
+            && let Some(complex_type) = element
+                .children
+                .iter()
+                .filter_map(as_element)
+                .find(|child| local_name(&child.name) == "complexType")
+        {
+            self.declare(complex_type, &content, false, 0, schema);
+        }
+    }
+
+    fn declare_attribute(
+        &self,
+        attribute_declaration: &XmlElement,
+        context: &str,
+        schema: &mut XmlSchema,
+    ) {
+        let (name, definition) = match (
+            attribute(attribute_declaration, "name"),
+            attribute(attribute_declaration, "ref"),
+        ) {
+            (Some(name), _) => (name, Some(attribute_declaration)),
+            (None, Some(reference)) => {
+                let name = local_name(reference);
+                (name, self.attributes.get(name).copied())
+            }
+            (None, None) => return,
+        };
+        if let Some(attribute_type) =
+            definition.and_then(|definition| self.attribute_type(definition))
+        {
+            schema
+                .attributes
+                .insert((context.to_string(), name.to_string()), attribute_type);
+        }
+    }
+
+    /// Declares children and attributes of complex type in `context`. Groups and base types are
+    /// declared in the same context, depth guards against their recursive definitions.
+    fn declare(
+        &self,
+        element: &XmlElement,
+        context: &str,
+        is_repeated: bool,
+        depth: usize,
+        schema: &mut XmlSchema,
+    ) {
+        if depth > 32 {
+            return;
+        }
+        for child in element.children.iter().filter_map(as_element) {
+            match local_name(&child.name) {
+                "element" => self.declare_element(child, context, is_repeated, schema),
+                "attribute" => self.declare_attribute(child, context, schema),
+                "sequence" | "choice" | "all" => {
+                    let is_repeated = is_repeated || max_occurs(child) > 1;
+                    self.declare(child, context, is_repeated, depth, schema);
+                }
+                "group" => {
+                    let group =
+                        attribute(child, "ref").and_then(|name| self.groups.get(local_name(name)));
+                    if let Some(group) = group {
+                        let is_repeated = is_repeated || max_occurs(child) > 1;
+                        self.declare(group, context, is_repeated, depth + 1, schema);
+                    }
+                }
+                "attributeGroup" => {
+                    let group = attribute(child, "ref")
+                        .and_then(|name| self.attribute_groups.get(local_name(name)));
+                    if let Some(group) = group {
+                        self.declare(group, context, false, depth + 1, schema);
+                    }
+                }
+                "extension" => {
+                    let base = attribute(child, "base")
+                        .and_then(|name| self.complex_types.get(local_name(name)));
+                    if let Some(base) = base {
+                        self.declare(base, context, false, depth + 1, schema);
+                    }
+                    self.declare(child, context, is_repeated, depth, schema);
+                }
+                // anonymous types of elements have their own context
+                "complexType" | "simpleType" => (),
+                _ => self.declare(child, context, is_repeated, depth, schema),
+            }
+        }
+    }
+
+    fn element_type(&self, element: &XmlElement) -> Option<SchemaType> {
+        if let Some(type_name) = attribute(element, "type") {
+            return self.named_type(type_name, 0);
+        }
+        let definition = element.children.iter().filter_map(as_element).next()?;
+        match local_name(&definition.name) {
+            "simpleType" => self.simple_type(definition, 0),
+            "complexType" => self.complex_type(definition, 0),
+            _ => None,
+        }
+    }
+
+    fn attribute_type(&self, attribute_declaration: &XmlElement) -> Option<SchemaType> {
+        if let Some(type_name) = attribute(attribute_declaration, "type") {
+            return self.named_type(type_name, 0);
+        }
+        let definition = attribute_declaration
+            .children
+            .iter()
+            .filter_map(as_element)
+            .find(|child| local_name(&child.name) == "simpleType")?;
+        self.simple_type(definition, 0)
+    }
+
+    /// Depth guards against recursive type definitions.
+    fn named_type(&self, type_name: &str, depth: usize) -> Option<SchemaType> {
+        if depth > 32 {
+            return None;
+        }
+        let name = local_name(type_name);
+        if let Some(simple_type) = self.simple_types.get(name) {
+            return self.simple_type(simple_type, depth + 1);
+        }
+        if let Some(complex_type) = self.complex_types.get(name) {
+            return self.complex_type(complex_type, depth + 1);
+        }
+        builtin_type(name)
+    }
+
+    fn simple_type(&self, definition: &XmlElement, depth: usize) -> Option<SchemaType> {
+        let content = definition.children.iter().filter_map(as_element).next()?;
+        match local_name(&content.name) {
+            "restriction" => match attribute(content, "base") {
+                Some(base) => self.named_type(base, depth + 1),
+                None => self.inline_simple_type(content, depth),
+            },
+            "list" => {
+                let item = match attribute(content, "itemType") {
+                    Some(item_type) => self.named_type(item_type, depth + 1),
+                    None => self.inline_simple_type(content, depth),
+                };
+                let item_type = item.map_or(ColumnType::String, |item| item.item_type);
+                Some(SchemaType {
+                    item_type,
+                    is_list: true,
+                })
+            }
+            // union members may have different types
+            _ => Some(SchemaType::item(ColumnType::String)),
+        }
+    }
+
+    fn inline_simple_type(&self, element: &XmlElement, depth: usize) -> Option<SchemaType> {
+        let definition = element
+            .children
+            .iter()
+            .filter_map(as_element)
+            .find(|child| local_name(&child.name) == "simpleType")?;
+        self.simple_type(definition, depth + 1)
+    }
+
+    /// Only complex types with simple content have typed text.
+    fn complex_type(&self, definition: &XmlElement, depth: usize) -> Option<SchemaType> {
+        let content = definition
+            .children
+            .iter()
+            .filter_map(as_element)
+            .find(|child| local_name(&child.name) == "simpleContent")?;
+        let derivation = content.children.iter().filter_map(as_element).next()?;
+        self.named_type(attribute(derivation, "base")?, depth + 1)
+    }
+}
+
+fn builtin_type(name: &str) -> Option<SchemaType> {
+    let item_type = match name {
+        "int" | "integer" | "long" | "short" | "byte" | "nonNegativeInteger"
+        | "positiveInteger" | "nonPositiveInteger" | "negativeInteger" | "unsignedLong"
+        | "unsignedInt" | "unsignedShort" | "unsignedByte" => ColumnType::Int,
+        "decimal" | "float" | "double" => ColumnType::Float,
+        "boolean" => ColumnType::Bool,
+        "date" | "dateTime" | "time" => ColumnType::Datetime,
+        "NMTOKENS" | "IDREFS" | "ENTITIES" => {
+            return Some(SchemaType {
+                item_type: ColumnType::String,
+                is_list: true,
+            });
+        }
+        "anyType" => return None,
+        _ => ColumnType::String,
+    };
+    Some(SchemaType::item(item_type))
+}
+
+fn as_element(node: &XmlNode) -> Option<&XmlElement> {
+    match node {
+        XmlNode::Element(element) => Some(element),
+        _ => None,
+    }
+}
+
+fn attribute<'a>(element: &'a XmlElement, name: &str) -> Option<&'a str> {
+    element
+        .attributes
+        .iter()
+        .find(|(key, _)| key == name)
+        .map(|(_, value)| value.as_str())
+}
+
+fn max_occurs(element: &XmlElement) -> u64 {
+    match attribute(element, "maxOccurs") {
+        Some("unbounded") => u64::MAX,
+        Some(value) => value.parse().unwrap_or(1),
+        None => 1,
+    }
+}
+
+/// Context of declarations in content of named complex type.
+fn type_context(name: &str) -> String {
+    format!("type:{name}")
+}
+
+/// Drops `prefix:` or `{uri}` of name.
+fn local_name(name: &str) -> &str {
+    let name = name.rsplit_once('}').map_or(name, |(_, local)| local);
+    name.rsplit_once(':').map_or(name, |(_, local)| local)
+}
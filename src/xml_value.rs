@@ -1,101 +1,546 @@
 use anyhow::{Context, Result, bail};
-use quick_xml::Reader;
-use quick_xml::events::Event;
+use quick_xml::escape::resolve_predefined_entity;
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::name::{NamespaceResolver, QName, ResolveResult};
+use quick_xml::{NsReader, XmlVersion};
 use serde::{Deserialize, Serialize};
 use serde_json::{Map, Value as JsonValue};
 
+use crate::formatting::Formatting;
+use crate::number::parse_number;
+use crate::xml_schema::{ElementDeclaration, SchemaType, XmlSchema};
+
 #[derive(Debug, Deserialize, Serialize)]
 pub struct XmlWrapper(serde_json::Value);
 
-pub fn load_xml(xml_str: &[u8]) -> Result<XmlWrapper> {
-    let mut reader = Reader::from_reader(xml_str);
+#[derive(Debug, Copy, Clone, Default, PartialEq, clap::ValueEnum)]
+pub enum XmlNamespaces {
+    /// Keep prefixed names like `soap:Envelope` and `xmlns` attributes as is
+    #[default]
+    Keep,
+    /// Resolve prefixes into `{uri}local` names and drop `xmlns` attributes
+    Expand,
+    /// Drop prefixes and `xmlns` attributes
+    Strip,
+}
 
-    let mut stack = Vec::new();
-    let mut current_map = Map::new();
-    let mut current_name = String::new();
-    let mut buffer = String::new();
+/// Convention of mapping xml elements to json values.
+#[derive(Debug, Copy, Clone, Default, PartialEq, clap::ValueEnum)]
+pub enum XmlConvention {
+    /// `@attr` and `#text` keys, elements with text only become values, `<root>` is unwrapped
+    #[default]
+    Default,
+    /// Every element is an object with `@attr` keys, text in `$` and namespaces in `@xmlns`
+    Badgerfish,
+    /// Attributes are dropped, elements with text only become values, root element is omitted
+    Parker,
+    /// Attributes are plain keys, text is in `$t` and `prefix:name` becomes `prefix$name`
+    Gdata,
+    /// Array of ordered nodes: elements `{"name", "attributes", "children"}`, text strings,
+    /// `{"comment"}`, `{"cdata"}`, `{"pi"}` and `{"doctype"}`. Whitespace is kept as is
+    Lossless,
+}
 
-    loop {
-        match reader.read_event() {
-            Ok(Event::Start(e)) => {
-                let name = e.name().as_ref().to_vec();
-                let name = String::from_utf8_lossy(&name).into_owned();
-                let mut attributes = Map::new();
-
-                for attr in e.attributes() {
-                    let attr = attr?;
-                    let key = attr.key.as_ref().to_vec();
-                    let key = String::from_utf8_lossy(&key).into_owned();
-                    let value = attr.value.as_ref().to_vec();
-                    let value = String::from_utf8_lossy(&value).into_owned();
-                    attributes.insert(format!("@{key}"), parse_value(&value));
-                }
+#[derive(Debug, Clone, Default)]
+pub struct XmlMapping {
+    pub convention: XmlConvention,
+    /// Prefix of attribute keys instead of convention's one
+    pub attr_prefix: Option<String>,
+    /// Key of element text instead of convention's one
+    pub text_key: Option<String>,
+    /// Names of elements loaded as arrays even when they appear once
+    pub force_arrays: Vec<String>,
+}
 
-                if !attributes.is_empty() {
-                    current_map.insert(name.clone(), JsonValue::Object(attributes));
-                }
+impl XmlMapping {
+    fn attr_prefix(&self) -> &str {
+        let default = match self.convention {
+            XmlConvention::Gdata => "",
+            _ => "@",
+        };
+        self.attr_prefix.as_deref().unwrap_or(default)
+    }
+
+    fn text_key(&self) -> &str {
+        let default = match self.convention {
+            XmlConvention::Default | XmlConvention::Parker | XmlConvention::Lossless => "#text",
+            XmlConvention::Badgerfish => "$",
+            XmlConvention::Gdata => "$t",
+        };
+        self.text_key.as_deref().unwrap_or(default)
+    }
+
+    /// Json key of xml name.
+    fn key(&self, name: &str) -> String {
+        match self.convention {
+            XmlConvention::Gdata => replace_prefix_separator(name, ":", "$"),
+            _ => name.to_string(),
+        }
+    }
+
+    /// Xml name of json key.
+    fn name(&self, key: &str) -> String {
+        match self.convention {
+            XmlConvention::Gdata => replace_prefix_separator(key, "$", ":"),
+            _ => key.to_string(),
+        }
+    }
+}
+
+/// Replaces separator of prefix and local part, uri of `{uri}local` names is kept as is.
+fn replace_prefix_separator(name: &str, from: &str, to: &str) -> String {
+    let (uri, local) = match name.find('}') {
+        Some(end) if name.starts_with('{') => name.split_at(end + 1),
+        _ => ("", name),
+    };
+    format!("{uri}{}", local.replacen(from, to, 1))
+}
+
+#[derive(Debug, Clone)]
+pub struct XmlLoadOptions {
+    pub namespaces: XmlNamespaces,
+    pub mapping: XmlMapping,
+    /// Guess bool and number types of attributes and text, otherwise keep strings
+    pub infer_types: bool,
+    /// Types and arrays of elements and attributes, types aren't guessed for declared ones
+    pub schema: Option<XmlSchema>,
+}
+
+impl Default for XmlLoadOptions {
+    fn default() -> Self {
+        Self {
+            namespaces: XmlNamespaces::default(),
+            mapping: XmlMapping::default(),
+            infer_types: true,
+            schema: None,
+        }
+    }
+}
+
+impl XmlLoadOptions {
+    /// Schema declaration of element in content of its parent, elements without declared parent
+    /// are looked up among top-level ones.
+    fn declaration(
+        &self,
+        parent: Option<&ElementDeclaration>,
+        element: &XmlElement,
+    ) -> Option<&ElementDeclaration> {
+        self.schema.as_ref()?.element(parent, &element.name)
+    }
 
-                stack.push((current_map, current_name));
-                current_map = Map::new();
-                current_name = name;
+    fn is_array(&self, declaration: Option<&ElementDeclaration>, key: &str) -> bool {
+        self.mapping.force_arrays.iter().any(|name| name == key)
+            || declaration.is_some_and(ElementDeclaration::is_array)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct XmlDumpOptions {
+    pub mapping: XmlMapping,
+    /// Prefixes and uris of namespaces declared on root element. Empty prefix declares default
+    /// namespace. `{uri}local` names are written with these prefixes, unknown uris get `ns0`, `ns1`..
+    pub namespaces: Vec<(String, String)>,
+    /// Name of element wrapping objects without a name, `root` by default. Without it and
+    /// `item_name` a single top-level key is the name of root element
+    pub root: Option<String>,
+    /// Top-level array is wrapped into root element with items of this name, otherwise every item
+    /// is written as a separate root element
+    pub item_name: Option<String>,
+    /// Write `<?xml version="1.0" encoding="UTF-8"?>` declaration
+    pub declaration: bool,
+    /// Write empty elements as `<name/>` instead of `<name></name>`
+    pub self_closing: bool,
+}
+
+impl Default for XmlDumpOptions {
+    fn default() -> Self {
+        Self {
+            mapping: XmlMapping::default(),
+            namespaces: vec![],
+            root: None,
+            item_name: None,
+            declaration: false,
+            self_closing: true,
+        }
+    }
+}
+
+impl XmlDumpOptions {
+    fn root_name(&self) -> &str {
+        self.root.as_deref().unwrap_or("root")
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum XmlNode {
+    Element(XmlElement),
+    Text(String),
+    CData(String),
+    Comment(String),
+    /// Processing instruction with target, including `xml` declaration
+    ProcessingInstruction(String),
+    DocType(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct XmlElement {
+    pub(crate) name: String,
+    pub(crate) attributes: Vec<(String, String)>,
+    pub(crate) children: Vec<XmlNode>,
+}
+
+impl XmlElement {
+    fn new(name: String) -> Self {
+        Self {
+            name,
+            attributes: vec![],
+            children: vec![],
+        }
+    }
+}
+
+pub fn load_xml(xml_str: &[u8], options: &XmlLoadOptions) -> Result<XmlWrapper> {
+    let document = parse_xml(xml_str, options)?;
+    let mapping = &options.mapping;
+    let elements: Vec<&XmlElement> = document
+        .iter()
+        .filter_map(|node| match node {
+            XmlNode::Element(element) => Some(element),
+            _ => None,
+        })
+        .collect();
+    if elements.is_empty() {
+        bail!("Can't read xml");
+    }
+
+    if mapping.convention == XmlConvention::Lossless {
+        let nodes = document.iter().map(node_to_lossless).collect();
+        return Ok(XmlWrapper(JsonValue::Array(nodes)));
+    }
+
+    if mapping.convention == XmlConvention::Parker {
+        let mut values: Vec<JsonValue> = elements
+            .iter()
+            .map(|element| element_to_json(element, options.declaration(None, element), options))
+            .collect::<Result<_>>()?;
+        return match values.len() {
+            1 => Ok(XmlWrapper(values.remove(0))),
+            _ => Ok(XmlWrapper(JsonValue::Array(values))),
+        };
+    }
+
+    let mut root = Map::new();
+    for element in elements {
+        let declaration = options.declaration(None, element);
+        let value = element_to_json(element, declaration, options)?;
+        let key = mapping.key(&element.name);
+        let is_array = options.is_array(declaration, &key);
+        insert_child(&mut root, key, value, is_array);
+    }
+
+    // unpack root item
+    if mapping.convention == XmlConvention::Default
+        && root.len() == 1
+        && let Some(value) = root.remove("root")
+    {
+        return Ok(XmlWrapper(value));
+    }
+    Ok(XmlWrapper(JsonValue::Object(root)))
+}
+
+pub(crate) fn parse_xml(xml_str: &[u8], options: &XmlLoadOptions) -> Result<Vec<XmlNode>> {
+    let mut reader = NsReader::from_reader(xml_str);
+
+    let mut stack: Vec<XmlElement> = Vec::new();
+    let mut document = Vec::new();
+
+    loop {
+        match reader.read_event()? {
+            Event::Start(e) => {
+                stack.push(start_element(reader.resolver(), &e, options)?);
             }
-            Ok(Event::Text(e)) => {
-                let text = e.decode().context(format!("XML unescape error: {e:?}"))?;
-                buffer = text.into_owned();
+            Event::Empty(e) => {
+                let element = start_element(reader.resolver(), &e, options)?;
+                push_node(&mut stack, &mut document, XmlNode::Element(element));
             }
-            Ok(Event::CData(e)) => {
-                buffer = String::from_utf8_lossy(e.as_ref()).into_owned();
+            Event::End(_) => {
+                let element = stack.pop().context("Unexpected closing xml tag")?;
+                push_node(&mut stack, &mut document, XmlNode::Element(element));
             }
-            Ok(Event::End(_)) => {
-                let value = if current_map.is_empty() {
-                    parse_value(&buffer)
-                } else {
-                    if !buffer.trim().is_empty() {
-                        current_map
-                            .insert("#text".to_string(), JsonValue::String(buffer.to_string()));
+            Event::Text(e) => {
+                let text = e.decode().context(format!("XML unescape error: {e:?}"))?;
+                push_text(&mut stack, &mut document, &text);
+            }
+            Event::GeneralRef(e) => {
+                let text = match e.resolve_char_ref()? {
+                    Some(ch) => ch.to_string(),
+                    None => {
+                        let name = e.decode()?;
+                        resolve_predefined_entity(&name)
+                            .with_context(|| format!("Unknown xml entity: &{name};"))?
+                            .to_string()
                     }
-                    JsonValue::Object(current_map)
                 };
+                push_text(&mut stack, &mut document, &text);
+            }
+            Event::CData(e) => {
+                let text = String::from_utf8_lossy(e.as_ref()).into_owned();
+                push_node(&mut stack, &mut document, XmlNode::CData(text));
+            }
+            Event::Comment(e) => {
+                let text = String::from_utf8_lossy(e.as_ref()).into_owned();
+                push_node(&mut stack, &mut document, XmlNode::Comment(text));
+            }
+            Event::Decl(e) => {
+                let text = String::from_utf8_lossy(e.as_ref()).into_owned();
+                push_node(
+                    &mut stack,
+                    &mut document,
+                    XmlNode::ProcessingInstruction(text),
+                );
+            }
+            Event::PI(e) => {
+                let text = String::from_utf8_lossy(e.as_ref()).into_owned();
+                push_node(
+                    &mut stack,
+                    &mut document,
+                    XmlNode::ProcessingInstruction(text),
+                );
+            }
+            Event::DocType(e) => {
+                let text = String::from_utf8_lossy(e.as_ref()).into_owned();
+                push_node(&mut stack, &mut document, XmlNode::DocType(text));
+            }
+            Event::Eof => break,
+        }
+    }
 
-                let (mut parent_map, parent_name) = stack.pop().unwrap();
-
-                if parent_map.contains_key(&current_name) {
-                    let existing_value = parent_map.get_mut(&current_name).unwrap();
-                    if let JsonValue::Array(arr) = existing_value {
-                        arr.push(value);
-                    } else {
-                        let old_value = parent_map.remove(&current_name).unwrap();
-                        parent_map.insert(
-                            current_name.clone(),
-                            JsonValue::Array(vec![old_value, value]),
-                        );
-                    }
-                } else {
-                    parent_map.insert(current_name.clone(), value);
+    if !stack.is_empty() {
+        bail!("Can't read xml: unclosed tag");
+    }
+    Ok(document)
+}
+
+fn start_element(
+    resolver: &NamespaceResolver,
+    e: &BytesStart,
+    options: &XmlLoadOptions,
+) -> Result<XmlElement> {
+    let name = resolve_name(resolver, e.name(), false, options.namespaces)
+        .context("Invalid xml element name")?;
+    let mut element = XmlElement::new(name);
+
+    for attr in e.attributes() {
+        let attr = attr?;
+        if let Some(key) = resolve_name(resolver, attr.key, true, options.namespaces) {
+            let value = attr.normalized_value(XmlVersion::Implicit1_0)?.into_owned();
+            element.attributes.push((key, value));
+        }
+    }
+    Ok(element)
+}
+
+/// Returns `None` for namespace declarations which are dropped unless namespaces are kept.
+fn resolve_name(
+    resolver: &NamespaceResolver,
+    name: QName,
+    is_attribute: bool,
+    namespaces: XmlNamespaces,
+) -> Option<String> {
+    let raw = String::from_utf8_lossy(name.as_ref()).into_owned();
+    if namespaces == XmlNamespaces::Keep {
+        return Some(raw);
+    }
+    if name.as_namespace_binding().is_some() {
+        return None;
+    }
+
+    let (namespace, local) = resolver.resolve(name, !is_attribute);
+    let local = String::from_utf8_lossy(local.as_ref()).into_owned();
+    match (namespaces, namespace) {
+        (XmlNamespaces::Expand, ResolveResult::Bound(namespace)) => {
+            let uri = String::from_utf8_lossy(namespace.as_ref());
+            Some(format!("{{{uri}}}{local}"))
+        }
+        (XmlNamespaces::Expand, _) => Some(raw),
+        _ => Some(local),
+    }
+}
+
+fn push_node(stack: &mut [XmlElement], document: &mut Vec<XmlNode>, node: XmlNode) {
+    match stack.last_mut() {
+        Some(parent) => parent.children.push(node),
+        None => document.push(node),
+    }
+}
+
+/// Adjacent text and entities are merged into one node.
+fn push_text(stack: &mut [XmlElement], document: &mut Vec<XmlNode>, text: &str) {
+    let nodes = match stack.last_mut() {
+        Some(parent) => &mut parent.children,
+        None => document,
+    };
+    if let Some(XmlNode::Text(last)) = nodes.last_mut() {
+        last.push_str(text);
+    } else {
+        nodes.push(XmlNode::Text(text.to_string()));
+    }
+}
+
+fn node_to_lossless(node: &XmlNode) -> JsonValue {
+    let (key, value) = match node {
+        XmlNode::Element(element) => {
+            let mut map = Map::new();
+            map.insert("name".to_string(), JsonValue::String(element.name.clone()));
+            if !element.attributes.is_empty() {
+                let attributes = element
+                    .attributes
+                    .iter()
+                    .map(|(key, value)| (key.clone(), JsonValue::String(value.clone())))
+                    .collect();
+                map.insert("attributes".to_string(), JsonValue::Object(attributes));
+            }
+            if !element.children.is_empty() {
+                let children = element.children.iter().map(node_to_lossless).collect();
+                map.insert("children".to_string(), JsonValue::Array(children));
+            }
+            return JsonValue::Object(map);
+        }
+        XmlNode::Text(text) => return JsonValue::String(text.clone()),
+        XmlNode::CData(text) => ("cdata", text),
+        XmlNode::Comment(text) => ("comment", text),
+        XmlNode::ProcessingInstruction(text) => ("pi", text),
+        XmlNode::DocType(text) => ("doctype", text),
+    };
+    let mut map = Map::new();
+    map.insert(key.to_string(), JsonValue::String(value.clone()));
+    JsonValue::Object(map)
+}
+
+fn element_to_json(
+    element: &XmlElement,
+    declaration: Option<&ElementDeclaration>,
+    options: &XmlLoadOptions,
+) -> Result<JsonValue> {
+    let mapping = &options.mapping;
+    let convention = mapping.convention;
+    let attr_prefix = mapping.attr_prefix();
+
+    let mut map = Map::new();
+    if convention != XmlConvention::Parker {
+        for (key, value) in &element.attributes {
+            if convention == XmlConvention::Badgerfish
+                && let Some(prefix) = namespace_prefix(key)
+            {
+                let declarations = map
+                    .entry(format!("{attr_prefix}xmlns"))
+                    .or_insert_with(|| JsonValue::Object(Map::new()));
+                if let JsonValue::Object(declarations) = declarations {
+                    let prefix = if prefix.is_empty() { "$" } else { prefix };
+                    declarations.insert(prefix.to_string(), JsonValue::String(value.clone()));
                 }
+                continue;
+            }
+            let attribute_type = options
+                .schema
+                .as_ref()
+                .and_then(|schema| schema.attribute_type(declaration, key));
+            let value = convert_text(value, attribute_type, options)
+                .with_context(|| format!("Invalid xml attribute {key:?} of <{}>", element.name))?;
+            map.insert(format!("{attr_prefix}{}", mapping.key(key)), value);
+        }
+    }
 
-                current_map = parent_map;
-                current_name = parent_name;
-                buffer.clear();
+    let mut text = String::new();
+    let mut has_children = false;
+    for child in &element.children {
+        match child {
+            XmlNode::Element(child) => {
+                has_children = true;
+                let child_declaration = options.declaration(declaration, child);
+                let value = element_to_json(child, child_declaration, options)?;
+                let key = mapping.key(&child.name);
+                let is_array = options.is_array(child_declaration, &key);
+                insert_child(&mut map, key, value, is_array);
             }
-            Ok(Event::Eof) => break,
-            Err(e) => return Err(e.into()),
+            XmlNode::Text(t) | XmlNode::CData(t) => text.push_str(t),
+            // comments and processing instructions are kept only by lossless convention
             _ => (),
         }
     }
 
-    if stack.is_empty() && !current_map.is_empty() {
-        // unpack root item
-        if let Some(root) = current_map.get("root") {
-            Ok(XmlWrapper(root.clone()))
-        } else {
-            Ok(XmlWrapper(JsonValue::Object(current_map)))
+    let element_type = declaration.and_then(ElementDeclaration::element_type);
+    let convert = |text: &str| {
+        convert_text(text, element_type, options)
+            .with_context(|| format!("Invalid xml element <{}>", element.name))
+    };
+    let value = match convention {
+        XmlConvention::Default | XmlConvention::Parker if map.is_empty() && !has_children => {
+            convert(&text)?
         }
-    } else {
-        bail!("Can't read xml");
+        // mixed text is dropped by parker
+        XmlConvention::Parker | XmlConvention::Lossless => JsonValue::Object(map),
+        XmlConvention::Default => {
+            if !text.trim().is_empty() {
+                let value = match element_type {
+                    Some(_) => convert(&text)?,
+                    None => JsonValue::String(text),
+                };
+                map.insert(mapping.text_key().to_string(), value);
+            }
+            JsonValue::Object(map)
+        }
+        XmlConvention::Badgerfish | XmlConvention::Gdata => {
+            if !text.trim().is_empty() {
+                map.insert(mapping.text_key().to_string(), convert(&text)?);
+            }
+            JsonValue::Object(map)
+        }
+    };
+    Ok(value)
+}
+
+/// Returns prefix declared by `xmlns:prefix` attribute, empty one for `xmlns`.
+fn namespace_prefix(attribute: &str) -> Option<&str> {
+    match attribute {
+        "xmlns" => Some(""),
+        attribute => attribute.strip_prefix("xmlns:"),
     }
 }
 
+/// Repeated elements are collected into array, `is_array` ones even when they appear once.
+fn insert_child(map: &mut Map<String, JsonValue>, key: String, value: JsonValue, is_array: bool) {
+    match map.get_mut(&key) {
+        Some(JsonValue::Array(arr)) => arr.push(value),
+        Some(existing) => {
+            let old_value = existing.take();
+            *existing = JsonValue::Array(vec![old_value, value]);
+        }
+        None if is_array => {
+            map.insert(key, JsonValue::Array(vec![value]));
+        }
+        None => {
+            map.insert(key, value);
+        }
+    }
+}
+
+fn convert_text(
+    s: &str,
+    schema_type: Option<SchemaType>,
+    options: &XmlLoadOptions,
+) -> Result<JsonValue> {
+    if let Some(schema_type) = schema_type {
+        return schema_type
+            .convert(s)
+            .with_context(|| format!("Value {s:?} doesn't match xml schema type"));
+    }
+    if options.infer_types {
+        return Ok(parse_value(s));
+    }
+    Ok(JsonValue::String(s.to_string()))
+}
+
 fn parse_value(s: &str) -> JsonValue {
     if s.is_empty() {
         return JsonValue::Null;
@@ -103,109 +548,387 @@ fn parse_value(s: &str) -> JsonValue {
     if let Ok(b) = s.parse::<bool>() {
         return JsonValue::Bool(b);
     }
-    if let Ok(i) = s.parse::<i64>() {
-        return JsonValue::Number(i.into());
-    }
-    if let Ok(f) = s.parse::<f64>()
-        && f.is_finite()
-    {
-        return JsonValue::Number(serde_json::Number::from_f64(f).unwrap());
+    if let Some(n) = parse_number(s) {
+        return JsonValue::Number(n);
     }
     JsonValue::String(s.to_string())
 }
 
-pub fn json_to_xml(json: &[u8]) -> Result<Vec<u8>> {
+pub fn json_to_xml(
+    json: &[u8],
+    is_compact: bool,
+    options: &XmlDumpOptions,
+    formatting: &Formatting,
+) -> Result<Vec<u8>> {
     let xml: JsonValue = serde_json::from_slice(json)?;
-    let mut buffer = String::new();
-    dump_xml(&xml, &mut buffer, None)?;
-    Ok(buffer.into_bytes())
+
+    let mut namespaces = Namespaces {
+        declared: options.namespaces.clone(),
+    };
+    let mapping = &options.mapping;
+    let mut document = match &xml {
+        // single key is name of root element
+        JsonValue::Object(obj)
+            if obj.len() == 1
+                && match mapping.convention {
+                    XmlConvention::Badgerfish | XmlConvention::Gdata => true,
+                    // mirrors unpacking of root element by load_xml
+                    XmlConvention::Default => {
+                        options.root.is_none()
+                            && options.item_name.is_none()
+                            && !obj.values().any(JsonValue::is_array)
+                    }
+                    _ => false,
+                } =>
+        {
+            let (name, value) = obj.iter().next().unwrap();
+            json_to_nodes(value, Some(name), options, &mut namespaces)
+        }
+        JsonValue::Array(nodes) if mapping.convention == XmlConvention::Lossless => nodes
+            .iter()
+            .map(|node| lossless_to_node(node, &mut namespaces))
+            .collect::<Result<_>>()?,
+        node if mapping.convention == XmlConvention::Lossless => {
+            vec![lossless_to_node(node, &mut namespaces)?]
+        }
+        JsonValue::Array(items) if options.item_name.is_some() => {
+            let item_name = options.item_name.as_deref();
+            let mut root = XmlElement::new(namespaces.qualify(options.root_name(), false));
+            for item in items {
+                let children = json_to_nodes(item, item_name, options, &mut namespaces);
+                root.children.extend(children);
+            }
+            vec![XmlNode::Element(root)]
+        }
+        xml => json_to_nodes(xml, None, options, &mut namespaces),
+    };
+    for node in &mut document {
+        if let XmlNode::Element(element) = node {
+            namespaces.declare(element);
+        }
+    }
+
+    let mut xml = XmlWriter {
+        buffer: String::new(),
+        // whitespace of lossless documents is kept as is
+        is_compact: is_compact || mapping.convention == XmlConvention::Lossless,
+        self_closing: options.self_closing,
+        indent: formatting.indent("  "),
+        depth: 0,
+    };
+    let has_declaration = matches!(
+        document.first(),
+        Some(XmlNode::ProcessingInstruction(pi)) if pi.starts_with("xml ")
+    );
+    if options.declaration && !has_declaration {
+        xml.line(r#"<?xml version="1.0" encoding="UTF-8"?>"#);
+    }
+    for node in &document {
+        xml.node(node);
+    }
+    Ok(xml.buffer.into_bytes())
+}
+
+fn lossless_to_node(value: &JsonValue, namespaces: &mut Namespaces) -> Result<XmlNode> {
+    let obj = match value {
+        JsonValue::String(text) => return Ok(XmlNode::Text(text.clone())),
+        JsonValue::Object(obj) => obj,
+        value => bail!("Invalid lossless xml node: {value}"),
+    };
+
+    if let Some(name) = obj.get("name") {
+        let Some(name) = name.as_str() else {
+            bail!("Invalid lossless xml element name: {name}");
+        };
+        let mut element = XmlElement::new(namespaces.qualify(name, false));
+        if let Some(attributes) = obj.get("attributes") {
+            let Some(attributes) = attributes.as_object() else {
+                bail!("Invalid lossless xml attributes of {name}: {attributes}");
+            };
+            for (key, value) in attributes {
+                let Some(value) = scalar_text(value) else {
+                    bail!("Invalid lossless xml attribute {key}: {value}");
+                };
+                element
+                    .attributes
+                    .push((namespaces.qualify(key, true), value));
+            }
+        }
+        if let Some(children) = obj.get("children") {
+            let Some(children) = children.as_array() else {
+                bail!("Invalid lossless xml children of {name}: {children}");
+            };
+            for child in children {
+                element.children.push(lossless_to_node(child, namespaces)?);
+            }
+        }
+        return Ok(XmlNode::Element(element));
+    }
+
+    let node = match obj.iter().next() {
+        Some((key, JsonValue::String(text))) if obj.len() == 1 => match key.as_str() {
+            "cdata" => XmlNode::CData(text.clone()),
+            "comment" => XmlNode::Comment(text.clone()),
+            "pi" => XmlNode::ProcessingInstruction(text.clone()),
+            "doctype" => XmlNode::DocType(text.clone()),
+            _ => bail!("Invalid lossless xml node: {value}"),
+        },
+        _ => bail!("Invalid lossless xml node: {value}"),
+    };
+    Ok(node)
+}
+
+struct Namespaces {
+    declared: Vec<(String, String)>,
+}
+
+impl Namespaces {
+    /// Replaces `{uri}` of expanded name with declared prefix.
+    fn qualify(&mut self, name: &str, is_attribute: bool) -> String {
+        let Some((uri, local)) = name.strip_prefix('{').and_then(|name| name.split_once('}'))
+        else {
+            return name.to_string();
+        };
+
+        // default namespace doesn't apply to attributes
+        let prefix = self
+            .declared
+            .iter()
+            .find(|(prefix, declared)| declared == uri && !(is_attribute && prefix.is_empty()))
+            .map(|(prefix, _)| prefix.clone());
+        let prefix = prefix.unwrap_or_else(|| {
+            let prefix = (0..)
+                .map(|n| format!("ns{n}"))
+                .find(|p| self.declared.iter().all(|(prefix, _)| prefix != p))
+                .unwrap_or_default();
+            self.declared.push((prefix.clone(), uri.to_string()));
+            prefix
+        });
+
+        if prefix.is_empty() {
+            local.to_string()
+        } else {
+            format!("{prefix}:{local}")
+        }
+    }
+
+    /// Adds `xmlns` attributes to root element.
+    fn declare(&self, element: &mut XmlElement) {
+        let declarations = self.declared.iter().filter_map(|(prefix, uri)| {
+            let key = if prefix.is_empty() {
+                "xmlns".to_string()
+            } else {
+                format!("xmlns:{prefix}")
+            };
+            let is_declared = element.attributes.iter().any(|(name, _)| *name == key);
+            (!is_declared).then(|| (key, uri.clone()))
+        });
+        let declarations: Vec<_> = declarations.collect();
+        element.attributes.splice(0..0, declarations);
+    }
 }
 
-fn dump_xml(value: &JsonValue, xml: &mut String, name: Option<&str>) -> Result<()> {
+fn json_to_nodes(
+    value: &JsonValue,
+    name: Option<&str>,
+    options: &XmlDumpOptions,
+    namespaces: &mut Namespaces,
+) -> Vec<XmlNode> {
+    let mapping = &options.mapping;
     match value {
         JsonValue::Object(obj) => {
-            let tag_name = name.unwrap_or("root");
-            xml.push_str(&format!("<{tag_name}"));
-
-            // Handle attributes
-            let mut has_children = false;
+            let name = name.map_or_else(
+                || options.root_name().to_string(),
+                |name| mapping.name(name),
+            );
+            let mut element = XmlElement::new(namespaces.qualify(&name, false));
             let mut text_content = None;
+            let attr_prefix = mapping.attr_prefix();
+            let xmlns_key = format!("{attr_prefix}xmlns");
 
             for (key, val) in obj {
-                if let Some(attr_name) = key.strip_prefix('@') {
-                    if let JsonValue::String(attr_val) = val {
-                        xml.push_str(&format!(" {}=\"{}\"", attr_name, escape_xml(attr_val)));
-                    }
-                } else if key == "#text"
-                    && let JsonValue::String(text) = val
+                if mapping.convention == XmlConvention::Parker {
+                    let children = json_to_nodes(val, Some(key), options, namespaces);
+                    element.children.extend(children);
+                } else if key == mapping.text_key() {
+                    text_content = scalar_text(val);
+                } else if mapping.convention == XmlConvention::Badgerfish
+                    && *key == xmlns_key
+                    && let JsonValue::Object(declarations) = val
                 {
-                    text_content = Some(text);
-                }
-            }
-
-            xml.push('>');
-
-            // Handle child elements and text content
-            for (key, val) in obj {
-                if !key.starts_with('@') && key != "#text" {
-                    has_children = true;
-                    if let JsonValue::Array(arr) = val {
-                        for item in arr {
-                            dump_xml(item, xml, Some(key))?;
+                    for (prefix, uri) in declarations {
+                        let attr_name = match prefix.as_str() {
+                            "$" => "xmlns".to_string(),
+                            prefix => format!("xmlns:{prefix}"),
+                        };
+                        if let Some(uri) = scalar_text(uri) {
+                            element.attributes.push((attr_name, uri));
                         }
-                    } else {
-                        dump_xml(val, xml, Some(key))?;
                     }
+                } else if attr_prefix.is_empty() {
+                    // without prefix scalars are attributes
+                    match val {
+                        JsonValue::Object(_) | JsonValue::Array(_) | JsonValue::Null => {
+                            let children = json_to_nodes(val, Some(key), options, namespaces);
+                            element.children.extend(children);
+                        }
+                        scalar => {
+                            let attr_name = namespaces.qualify(&mapping.name(key), true);
+                            let attr_val = scalar_text(scalar).unwrap_or_default();
+                            element.attributes.push((attr_name, attr_val));
+                        }
+                    }
+                } else if let Some(attr_name) = key.strip_prefix(attr_prefix) {
+                    // nested values can't be written as attributes
+                    let attr_val = match val {
+                        JsonValue::Null => Some(String::new()),
+                        val => scalar_text(val),
+                    };
+                    if let Some(attr_val) = attr_val {
+                        let attr_name = namespaces.qualify(&mapping.name(attr_name), true);
+                        element.attributes.push((attr_name, attr_val));
+                    }
+                } else {
+                    let children = json_to_nodes(val, Some(key), options, namespaces);
+                    element.children.extend(children);
                 }
             }
 
             if let Some(text) = text_content {
-                xml.push_str(&escape_xml(text));
-            } else if !has_children && text_content.is_none() {
-                // Self-closing tag if no content
-                xml.pop(); // Remove '>'
-                xml.push_str("/>");
-                return Ok(());
+                element.children.push(XmlNode::Text(text));
             }
-
-            xml.push_str(&format!("</{tag_name}>"));
+            vec![XmlNode::Element(element)]
         }
-        JsonValue::Array(arr) => {
-            for item in arr {
-                dump_xml(item, xml, name)?;
+        JsonValue::Array(arr) => arr
+            .iter()
+            .flat_map(|item| json_to_nodes(item, name, options, namespaces))
+            .collect(),
+        JsonValue::Null => match name {
+            Some(name) => {
+                let name = namespaces.qualify(&mapping.name(name), false);
+                vec![XmlNode::Element(XmlElement::new(name))]
             }
-        }
-        JsonValue::String(s) => {
-            if let Some(tag_name) = name {
-                xml.push_str(&format!("<{}>{}</{}>", tag_name, escape_xml(s), tag_name));
-            } else {
-                xml.push_str(&escape_xml(s));
+            None => vec![],
+        },
+        scalar => {
+            let text = scalar_text(scalar).unwrap_or_default();
+            match name {
+                Some(name) => {
+                    let name = namespaces.qualify(&mapping.name(name), false);
+                    let mut element = XmlElement::new(name);
+                    element.children.push(XmlNode::Text(text));
+                    vec![XmlNode::Element(element)]
+                }
+                None => vec![XmlNode::Text(text)],
             }
         }
-        JsonValue::Number(n) => {
-            if let Some(tag_name) = name {
-                xml.push_str(&format!("<{tag_name}>{n}</{tag_name}>"));
-            } else {
-                xml.push_str(&n.to_string());
-            }
+    }
+}
+
+fn scalar_text(value: &JsonValue) -> Option<String> {
+    match value {
+        JsonValue::String(s) => Some(s.clone()),
+        JsonValue::Bool(_) | JsonValue::Number(_) => Some(value.to_string()),
+        JsonValue::Null | JsonValue::Array(_) | JsonValue::Object(_) => None,
+    }
+}
+
+struct XmlWriter {
+    buffer: String,
+    is_compact: bool,
+    self_closing: bool,
+    indent: String,
+    depth: usize,
+}
+
+impl XmlWriter {
+    fn line(&mut self, content: &str) {
+        if !self.is_compact {
+            self.buffer.push_str(&self.indent.repeat(self.depth));
         }
-        JsonValue::Bool(b) => {
-            if let Some(tag_name) = name {
-                xml.push_str(&format!("<{tag_name}>{b}</{tag_name}>"));
-            } else {
-                xml.push_str(&b.to_string());
-            }
+        self.buffer.push_str(content);
+        if !self.is_compact {
+            self.buffer.push('\n');
         }
-        JsonValue::Null => {
-            if let Some(tag_name) = name {
-                xml.push_str(&format!("<{tag_name}/>"));
+    }
+
+    fn node(&mut self, node: &XmlNode) {
+        match node {
+            XmlNode::Element(element) => self.element(element),
+            // whitespace between elements is replaced by indentation
+            XmlNode::Text(text) if !self.is_compact && text.trim().is_empty() => (),
+            node => {
+                let mut buffer = String::new();
+                self.inline(node, &mut buffer);
+                self.line(&buffer);
             }
         }
     }
-    Ok(())
+
+    /// Elements with text are written in one line to keep their content as is.
+    fn element(&mut self, element: &XmlElement) {
+        let is_whitespace = |child: &XmlNode| match child {
+            XmlNode::Text(text) => text.trim().is_empty(),
+            _ => false,
+        };
+        let has_text = element.children.iter().any(|child| match child {
+            XmlNode::Text(_) => !is_whitespace(child),
+            XmlNode::CData(_) => true,
+            _ => false,
+        });
+        let has_markup = !element.children.iter().all(is_whitespace);
+        if self.is_compact || has_text || !has_markup {
+            let mut buffer = String::new();
+            self.inline_element(element, &mut buffer);
+            self.line(&buffer);
+            return;
+        }
+
+        self.line(&format!("<{}>", self.start_tag(element)));
+        self.depth += 1;
+        for child in &element.children {
+            self.node(child);
+        }
+        self.depth -= 1;
+        self.line(&format!("</{}>", element.name));
+    }
+
+    fn inline(&self, node: &XmlNode, buffer: &mut String) {
+        match node {
+            XmlNode::Element(element) => self.inline_element(element, buffer),
+            XmlNode::Text(text) => buffer.push_str(&escape_xml(text)),
+            XmlNode::CData(text) => buffer.push_str(&format!("<![CDATA[{text}]]>")),
+            XmlNode::Comment(text) => buffer.push_str(&format!("<!--{text}-->")),
+            XmlNode::ProcessingInstruction(text) => buffer.push_str(&format!("<?{text}?>")),
+            XmlNode::DocType(text) => buffer.push_str(&format!("<!DOCTYPE {text}>")),
+        }
+    }
+
+    fn inline_element(&self, element: &XmlElement, buffer: &mut String) {
+        buffer.push('<');
+        buffer.push_str(&self.start_tag(element));
+        if element.children.is_empty() && self.self_closing {
+            // Self-closing tag if no content
+            buffer.push_str("/>");
+            return;
+        }
+        buffer.push('>');
+        for child in &element.children {
+            self.inline(child, buffer);
+        }
+        buffer.push_str(&format!("</{}>", element.name));
+    }
+
+    fn start_tag(&self, element: &XmlElement) -> String {
+        let mut tag = element.name.clone();
+        for (key, value) in &element.attributes {
+            tag.push_str(&format!(" {key}=\"{}\"", escape_xml(value)));
+        }
+        tag
+    }
 }
 
-fn escape_xml(s: &str) -> String {
+pub(crate) fn escape_xml(s: &str) -> String {
     s.replace('&', "&amp;")
         .replace('<', "&lt;")
         .replace('>', "&gt;")
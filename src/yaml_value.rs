@@ -0,0 +1,148 @@
+use anyhow::Result;
+use serde_json::{Number, Value as JsonValue};
+use serde_yaml::Value as YamlValue;
+
+use crate::NativeValue;
+use crate::number::{NativeNumbers, is_native};
+
+/// Yaml in block style. Numbers of json which don't fit into integers and floats are written
+/// with all of their digits.
+pub(crate) fn to_yaml(value: &NativeValue) -> Result<Vec<u8>> {
+    let NativeValue::Json { json, is_i64_only } = value else {
+        return Ok(serde_yaml::to_string(value)?.into_bytes());
+    };
+    // such numbers are serialized as plain placeholders which aren't in any string of value
+    let text = json.to_string();
+    let prefix = (0..)
+        .map(|n| format!("number{n}_"))
+        .find(|prefix| !text.contains(prefix.as_str()))
+        .unwrap_or_default();
+    let mut numbers = vec![];
+    let json = replace_numbers(json.clone(), &prefix, &mut numbers);
+
+    let mut yaml = serde_yaml::to_string(&NativeNumbers::new(&json, *is_i64_only))?;
+    for (i, n) in numbers.iter().enumerate() {
+        yaml = yaml.replacen(&format!("{prefix}{i}_"), n.as_str(), 1);
+    }
+    Ok(yaml.into_bytes())
+}
+
+fn replace_numbers(json: JsonValue, prefix: &str, numbers: &mut Vec<Number>) -> JsonValue {
+    match json {
+        JsonValue::Number(n) if !is_native(&n) => {
+            let placeholder = format!("{prefix}{}_", numbers.len());
+            numbers.push(n);
+            JsonValue::String(placeholder)
+        }
+        JsonValue::Array(items) => JsonValue::Array(
+            items
+                .into_iter()
+                .map(|item| replace_numbers(item, prefix, numbers))
+                .collect(),
+        ),
+        JsonValue::Object(map) => JsonValue::Object(
+            map.into_iter()
+                .map(|(key, value)| (key, replace_numbers(value, prefix, numbers)))
+                .collect(),
+        ),
+        json => json,
+    }
+}
+
+/// Yaml in flow style on one line, like `{name: Frodo, rings: [1, 2]}`.
+pub(crate) fn to_yaml_flow(value: &NativeValue) -> Result<Vec<u8>> {
+    let mut buffer = String::new();
+    match value {
+        NativeValue::Value(value) => write_flow(&serde_yaml::to_value(value)?, &mut buffer)?,
+        NativeValue::Json { json, is_i64_only } => {
+            write_json_flow(json, *is_i64_only, &mut buffer)?
+        }
+    }
+    buffer.push('\n');
+    Ok(buffer.into_bytes())
+}
+
+/// Yaml values hold only 64-bit numbers, so numbers of json are written one by one.
+fn write_json_flow(json: &JsonValue, is_i64_only: bool, buffer: &mut String) -> Result<()> {
+    match json {
+        JsonValue::Number(n) if !is_native(n) => buffer.push_str(n.as_str()),
+        JsonValue::Number(_) => {
+            match serde_yaml::to_value(NativeNumbers::new(json, is_i64_only))? {
+                // larger integers are serialized into strings, yaml writes them unquoted
+                YamlValue::String(n) => buffer.push_str(&n),
+                yaml => write_flow(&yaml, buffer)?,
+            }
+        }
+        JsonValue::Array(items) => {
+            buffer.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    buffer.push_str(", ");
+                }
+                write_json_flow(item, is_i64_only, buffer)?;
+            }
+            buffer.push(']');
+        }
+        JsonValue::Object(map) => {
+            buffer.push('{');
+            for (i, (key, value)) in map.iter().enumerate() {
+                if i > 0 {
+                    buffer.push_str(", ");
+                }
+                buffer.push_str(&flow_string(key)?);
+                buffer.push_str(": ");
+                write_json_flow(value, is_i64_only, buffer)?;
+            }
+            buffer.push('}');
+        }
+        json => write_flow(&serde_yaml::to_value(json)?, buffer)?,
+    }
+    Ok(())
+}
+
+fn write_flow(yaml: &YamlValue, buffer: &mut String) -> Result<()> {
+    match yaml {
+        YamlValue::Null => buffer.push_str("null"),
+        YamlValue::Bool(b) => buffer.push_str(&b.to_string()),
+        YamlValue::Number(n) => buffer.push_str(&n.to_string()),
+        YamlValue::String(s) => buffer.push_str(&flow_string(s)?),
+        YamlValue::Sequence(items) => {
+            buffer.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    buffer.push_str(", ");
+                }
+                write_flow(item, buffer)?;
+            }
+            buffer.push(']');
+        }
+        YamlValue::Mapping(map) => {
+            buffer.push('{');
+            for (i, (key, value)) in map.iter().enumerate() {
+                if i > 0 {
+                    buffer.push_str(", ");
+                }
+                write_flow(key, buffer)?;
+                buffer.push_str(": ");
+                write_flow(value, buffer)?;
+            }
+            buffer.push('}');
+        }
+        YamlValue::Tagged(tagged) => {
+            buffer.push_str(&format!("{} ", tagged.tag));
+            write_flow(&tagged.value, buffer)?;
+        }
+    }
+    Ok(())
+}
+
+/// Strings are written like in block style unless they have line breaks or flow indicators,
+/// such strings are double-quoted with json escapes, which are valid in yaml.
+fn flow_string(s: &str) -> Result<String> {
+    let block = serde_yaml::to_string(s)?;
+    let block = block.trim_end_matches('\n');
+    if block.contains(['\n', ',', '[', ']', '{', '}']) {
+        return Ok(serde_json::to_string(s)?);
+    }
+    Ok(block.to_string())
+}